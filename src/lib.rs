@@ -18,6 +18,7 @@
 //!    Family,
 //!    Protocol,
 //!    Type,
+//!    ConnectState,
 //!    select
 //!};
 //!
@@ -33,10 +34,25 @@
 //!
 //!    let dest = net::SocketAddr::from_str("192.168.0.1:80").unwrap();
 //!
-//!    let _ = socket.set_blocking(false);
-//!    let _ = socket.connect(&dest);
+//!    let pending = match socket.connect_nonblocking(&dest) {
+//!        Ok(ConnectState::Connected) => {
+//!            println!("Connected!");
+//!            return;
+//!        },
+//!        Ok(ConnectState::InProgress(pending)) => pending,
+//!        Err(error) => {
+//!            println!("Failed to connect. Error:{}", error);
+//!            return;
+//!        }
+//!    };
+//!
 //!    match select(&[], &[&socket], &[&socket], Some(timeout)) {
-//!          Ok(_) => println!("Connected!"),
+//!          Ok(0) => println!("Timed out!"),
+//!          Ok(_) => match pending.poll_complete() {
+//!              Ok(true) => println!("Connected!"),
+//!              Ok(false) => println!("Still connecting..."),
+//!              Err(error) => println!("Failed to connect. Error:{}", error)
+//!          },
 //!          Err(error) => println!("Failed to connect. Error:{}", error)
 //!    }
 //!}
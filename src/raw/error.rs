@@ -0,0 +1,287 @@
+//! Error classification.
+//!
+//! Socket calls, such as `bind`, `connect`, `send` or `recv`, return `SocketError` that records failed operation,
+//! while option accessors return plain `io::Error`. Raw OS error codes stay available in both.
+//! This module provides means to reason about them without matching on raw codes.
+use std::io;
+use std::fmt;
+use std::error;
+
+use super::classify_os_error;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Class of socket error.
+pub enum ErrorClass {
+    ///Operation can be retried as is, e.g. it would block, was interrupted or ran out of buffers.
+    Transient,
+    ///Connection was reset, refused or closed by peer.
+    PeerClosed,
+    ///Address is invalid, unavailable or unreachable.
+    Address,
+    ///Operation is not permitted.
+    Permission,
+    ///Socket is misused, e.g. invalid argument or unsupported option.
+    Programmer,
+    ///Error that doesn't fall into any other class.
+    Other
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Socket operation.
+pub enum Operation {
+    ///Creation of socket.
+    Create,
+    ///Retrieval of socket's name.
+    Name,
+    ///Binding of socket.
+    Bind,
+    ///Listening for incoming connections.
+    Listen,
+    ///Accepting incoming connection.
+    Accept,
+    ///Connecting to remote address.
+    Connect,
+    ///Receiving data.
+    Recv,
+    ///Sending data.
+    Send,
+    ///Retrieving socket option.
+    GetOpt,
+    ///Setting socket option.
+    SetOpt,
+    ///Controlling socket I/O parameters.
+    Ioctl,
+    ///Waiting for socket readiness.
+    Select,
+    ///Shutting down socket.
+    Shutdown,
+    ///Closing socket.
+    Close
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Operation::Create => "socket",
+            Operation::Name => "getsockname",
+            Operation::Bind => "bind",
+            Operation::Listen => "listen",
+            Operation::Accept => "accept",
+            Operation::Connect => "connect",
+            Operation::Recv => "recv",
+            Operation::Send => "send",
+            Operation::GetOpt => "getsockopt",
+            Operation::SetOpt => "setsockopt",
+            Operation::Ioctl => "ioctl",
+            Operation::Select => "select",
+            Operation::Shutdown => "shutdown",
+            Operation::Close => "close",
+        };
+
+        f.write_str(name)
+    }
+}
+
+///Error of socket operation.
+///
+///Returned by socket calls, while `io::Error` of other methods can be tagged using `ResultExt::operation`.
+#[derive(Debug)]
+pub struct SocketError {
+    operation: Operation,
+    error: io::Error
+}
+
+impl SocketError {
+    ///Creates new error of operation.
+    pub fn new(operation: Operation, error: io::Error) -> Self {
+        SocketError {
+            operation,
+            error
+        }
+    }
+
+    ///Creates error of operation from last OS error.
+    pub fn last_os_error(operation: Operation) -> Self {
+        SocketError::new(operation, io::Error::last_os_error())
+    }
+
+    ///Returns operation that failed.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    ///Returns class of error.
+    pub fn class(&self) -> ErrorClass {
+        self.error.class()
+    }
+
+    ///Returns kind of underlying error.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.error.kind()
+    }
+
+    ///Returns OS error code, if any.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.error.raw_os_error()
+    }
+
+    ///Returns reference to underlying error.
+    pub fn get_ref(&self) -> &io::Error {
+        &self.error
+    }
+
+    ///Returns underlying error.
+    pub fn into_inner(self) -> io::Error {
+        self.error
+    }
+}
+
+impl fmt::Display for SocketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} failed: {}", self.operation, self.error)
+    }
+}
+
+impl error::Error for SocketError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<SocketError> for io::Error {
+    ///Wraps error preserving its kind.
+    ///
+    ///Use `SocketError::into_inner` to keep OS error code instead.
+    fn from(error: SocketError) -> io::Error {
+        io::Error::new(error.kind(), error)
+    }
+}
+
+///Error of transfer that failed after some bytes were transferred.
+///
+///Returned by `Socket::send_all` and `Socket::recv_exact`.
+#[derive(Debug)]
+pub struct TransferError {
+    transferred: usize,
+    error: io::Error
+}
+
+impl TransferError {
+    ///Creates new error of transfer that moved `transferred` bytes.
+    pub fn new(transferred: usize, error: io::Error) -> Self {
+        TransferError {
+            transferred,
+            error
+        }
+    }
+
+    ///Returns number of bytes transferred before failure.
+    pub fn transferred(&self) -> usize {
+        self.transferred
+    }
+
+    ///Returns kind of underlying error.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.error.kind()
+    }
+
+    ///Returns OS error code, if any.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.error.raw_os_error()
+    }
+
+    ///Returns reference to underlying error.
+    pub fn get_ref(&self) -> &io::Error {
+        &self.error
+    }
+
+    ///Returns underlying error.
+    pub fn into_inner(self) -> io::Error {
+        self.error
+    }
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} after transferring {} bytes", self.error, self.transferred)
+    }
+}
+
+impl error::Error for TransferError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<TransferError> for io::Error {
+    ///Returns underlying error, discarding number of transferred bytes.
+    fn from(error: TransferError) -> io::Error {
+        error.error
+    }
+}
+
+impl ErrorExt for TransferError {
+    fn class(&self) -> ErrorClass {
+        self.error.class()
+    }
+}
+
+///Extension of `io::Error` to classify socket errors.
+pub trait ErrorExt {
+    ///Returns class of error.
+    fn class(&self) -> ErrorClass;
+
+    ///Returns whether operation can be retried as is.
+    fn is_transient(&self) -> bool {
+        self.class() == ErrorClass::Transient
+    }
+}
+
+impl ErrorExt for io::Error {
+    fn class(&self) -> ErrorClass {
+        if let Some(code) = self.raw_os_error() {
+            return classify_os_error(code);
+        }
+
+        if let Some(error) = self.get_ref().and_then(|error| error.downcast_ref::<SocketError>()) {
+            return error.class();
+        }
+
+        match self.kind() {
+            io::ErrorKind::WouldBlock |
+            io::ErrorKind::Interrupted |
+            io::ErrorKind::TimedOut => ErrorClass::Transient,
+            io::ErrorKind::ConnectionReset |
+            io::ErrorKind::ConnectionAborted |
+            io::ErrorKind::ConnectionRefused |
+            io::ErrorKind::BrokenPipe |
+            io::ErrorKind::NotConnected |
+            io::ErrorKind::UnexpectedEof |
+            io::ErrorKind::WriteZero => ErrorClass::PeerClosed,
+            io::ErrorKind::AddrInUse |
+            io::ErrorKind::AddrNotAvailable => ErrorClass::Address,
+            io::ErrorKind::PermissionDenied => ErrorClass::Permission,
+            io::ErrorKind::InvalidInput |
+            io::ErrorKind::InvalidData => ErrorClass::Programmer,
+            _ => ErrorClass::Other
+        }
+    }
+}
+
+impl ErrorExt for SocketError {
+    fn class(&self) -> ErrorClass {
+        self.error.class()
+    }
+}
+
+///Extension of `io::Result` to record failed operation.
+pub trait ResultExt<T> {
+    ///Records operation that produced error.
+    fn operation(self, operation: Operation) -> Result<T, SocketError>;
+}
+
+impl<T> ResultExt<T> for io::Result<T> {
+    fn operation(self, operation: Operation) -> Result<T, SocketError> {
+        self.map_err(|error| SocketError::new(operation, error))
+    }
+}
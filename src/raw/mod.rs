@@ -10,3 +10,6 @@ pub use self::unix::*;
 mod windows;
 #[cfg(windows)]
 pub use self::windows::*;
+
+mod error;
+pub use self::error::*;
@@ -3,6 +3,21 @@ use std::io;
 use std::mem;
 use std::ptr;
 use std::cmp;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::fs;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::slice;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::sync::atomic::AtomicU32;
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+use std::ffi::CStr;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::time::{Duration, Instant};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{ErrorClass, Operation, ResultExt, SocketError, TransferError};
 
 mod libc {
     extern crate libc;
@@ -11,18 +26,14 @@ mod libc {
     pub use self::libc::{
         c_int,
         c_void,
-        c_char,
-        c_long,
         c_ulong,
-        ssize_t,
+        c_uint,
         socklen_t,
-        size_t,
-        sockaddr,
         sockaddr_storage,
         sa_family_t,
-        in_port_t,
         fd_set,
         timeval,
+        linger,
         time_t,
         suseconds_t
     };
@@ -40,19 +51,156 @@ mod libc {
         in6_addr
     };
 
+    #[allow(clippy::upper_case_acronyms)]
     pub type SOCKET = c_int;
     pub const SOCKET_ERROR: c_int = -1;
     pub const SOCKET_SHUTDOWN: c_int = libc::ESHUTDOWN;
 
     //Constants
     pub use self::libc::{
+        EAGAIN,
+        EINTR,
+        EWOULDBLOCK,
+        ENOBUFS,
+        ENOMEM,
+        ETIMEDOUT,
+        ECONNRESET,
+        ECONNABORTED,
+        ECONNREFUSED,
+        EPIPE,
+        ENOTCONN,
+        EADDRINUSE,
+        EADDRNOTAVAIL,
+        EAFNOSUPPORT,
+        ENETUNREACH,
+        EHOSTUNREACH,
+        ENETDOWN,
+        EHOSTDOWN,
+        ENETRESET,
+        EDESTADDRREQ,
+        EACCES,
+        EPERM,
+        EBADF,
+        ENOTSOCK,
         EINVAL,
+        EFAULT,
+        EOPNOTSUPP,
+        ENOPROTOOPT,
+        EPROTONOSUPPORT,
+        EPROTOTYPE,
+        ESOCKTNOSUPPORT,
+        EMSGSIZE,
+
+        MSG_PEEK,
+        MSG_WAITALL,
+        MSG_DONTWAIT,
+        MSG_TRUNC,
+        MSG_OOB,
+        MSG_DONTROUTE,
+
+        EINPROGRESS,
+        EALREADY,
+        EISCONN,
+
         FIONBIO,
+        FIONREAD,
         F_GETFD,
         F_SETFD,
-        FD_CLOEXEC
+        F_GETFL,
+        O_NONBLOCK,
+        FD_CLOEXEC,
+
+        SOL_SOCKET,
+        SO_RCVTIMEO,
+        SO_SNDTIMEO,
+        SO_RCVBUF,
+        SO_SNDBUF,
+        SO_RCVLOWAT,
+        SO_SNDLOWAT,
+        SO_LINGER,
+        SO_REUSEADDR,
+        SO_BROADCAST,
+        SO_ERROR,
+
+        IPPROTO_IP,
+        IPPROTO_IPV6,
+        IPV6_V6ONLY,
+        IP_TTL,
+        IP_TOS,
+        IPV6_UNICAST_HOPS,
+        IPV6_TCLASS,
+
+        ip_mreq,
+        ipv6_mreq,
+        IP_ADD_MEMBERSHIP,
+        IP_DROP_MEMBERSHIP,
+        IP_MULTICAST_IF,
+        IP_MULTICAST_TTL,
+        IP_MULTICAST_LOOP,
+        IPV6_MULTICAST_IF,
+        IPV6_MULTICAST_HOPS,
+        IPV6_MULTICAST_LOOP
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub use self::libc::{
+        IPV6_ADD_MEMBERSHIP as IPV6_JOIN_GROUP,
+        IPV6_DROP_MEMBERSHIP as IPV6_LEAVE_GROUP
+    };
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub use self::libc::{
+        IPV6_JOIN_GROUP,
+        IPV6_LEAVE_GROUP
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+    pub use self::libc::{
+        SO_REUSEPORT
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+    pub use self::libc::{
+        sockaddr,
+        ifaddrs,
+        getifaddrs,
+        freeifaddrs,
+        IFF_BROADCAST
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub use self::libc::{
+        SO_RCVBUFFORCE,
+        SO_SNDBUFFORCE,
+
+        MSG_MORE,
+        MSG_CONFIRM,
+        MSG_ERRQUEUE,
+        MSG_FASTOPEN
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+    pub use self::libc::{
+        MSG_NOSIGNAL
+    };
+
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    pub use self::libc::{
+        SO_NOSIGPIPE
+    };
+
+    #[cfg(target_os = "linux")]
+    pub use self::libc::{
+        MSG_ZEROCOPY,
+        SO_ZEROCOPY
     };
 
+    //Not provided by libc for Android.
+    #[cfg(target_os = "android")]
+    pub const MSG_ZEROCOPY: c_int = 0x4000000;
+    #[cfg(target_os = "android")]
+    pub const SO_ZEROCOPY: c_int = 60;
+
     #[cfg(target_os = "macos")]
     pub use self::libc::{
         AF_UNIX,
@@ -91,10 +239,60 @@ mod libc {
         AF_PACKET,
     };
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub use self::libc::{
+        sock_filter,
+        sock_fprog,
+
+        SKF_AD_OFF,
+        SKF_AD_CPU,
+        SKF_NET_OFF,
+
+        BPF_LD,
+        BPF_ALU,
+        BPF_RET,
+        BPF_MISC,
+        BPF_W,
+        BPF_H,
+        BPF_B,
+        BPF_ABS,
+        BPF_IND,
+        BPF_AND,
+        BPF_LSH,
+        BPF_MOD,
+        BPF_K,
+        BPF_A,
+        BPF_TAX
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub use self::libc::{
+        SO_ATTACH_REUSEPORT_CBPF,
+        UDP_SEGMENT,
+        UDP_GRO,
+        SO_TIMESTAMPNS,
+        SCM_TIMESTAMPNS,
+        SO_TIMESTAMPING,
+        SCM_TIMESTAMPING
+    };
+
+    //Kernel defines SIOCOUTQ as alias of TIOCOUTQ.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub const SIOCOUTQ: IoctlRequestT = libc::TIOCOUTQ as IoctlRequestT;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub const SIOCOUTQNSD: IoctlRequestT = libc::SIOCOUTQNSD as IoctlRequestT;
+
+    //Not provided by libc.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub const SO_EE_ORIGIN_ZEROCOPY: u8 = 5;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub const SO_EE_CODE_ZEROCOPY_COPIED: u8 = 1;
+
     //Functions
     pub use self::libc::{
         socket,
         getsockname,
+        getpeername,
         bind,
         listen,
         recv,
@@ -113,10 +311,92 @@ mod libc {
         FD_SET
     };
 
-    #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd", target_os = "dragonflybsd"))]
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd", target_os = "dragonfly"))]
     pub use self::libc::{
         accept4
     };
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    pub use self::libc::{
+        ip_mreqn
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+    pub use self::libc::{
+        ip_mreq_source,
+        group_source_req,
+        IP_ADD_SOURCE_MEMBERSHIP,
+        IP_DROP_SOURCE_MEMBERSHIP,
+        MCAST_JOIN_SOURCE_GROUP,
+        MCAST_LEAVE_SOURCE_GROUP
+    };
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub use self::libc::{
+        off_t,
+        O_CLOEXEC,
+        SPLICE_F_MOVE,
+
+        msghdr,
+        iovec,
+        sock_extended_err,
+        SOL_IP,
+        SOL_IPV6,
+        SOL_UDP,
+        IP_RECVERR,
+        IPV6_RECVERR,
+        SO_EE_ORIGIN_NONE,
+        SO_EE_ORIGIN_LOCAL,
+        SO_EE_ORIGIN_ICMP,
+        SO_EE_ORIGIN_ICMP6,
+        SO_EE_ORIGIN_TXSTATUS,
+        in_pktinfo,
+        in6_pktinfo,
+        IP_PKTINFO,
+        IPV6_RECVPKTINFO,
+        IPV6_PKTINFO,
+        IP_RECVTTL,
+        IP_RECVTOS,
+        IPV6_RECVHOPLIMIT,
+        IPV6_HOPLIMIT,
+        IPV6_RECVTCLASS,
+        IP_MTU_DISCOVER,
+        IP_MTU,
+        IPV6_MTU_DISCOVER,
+        IPV6_MTU,
+        IP_PMTUDISC_DONT,
+        IP_PMTUDISC_WANT,
+        IP_PMTUDISC_DO,
+        IP_PMTUDISC_PROBE,
+        IP_PMTUDISC_INTERFACE,
+        timespec,
+        SOF_TIMESTAMPING_TX_SOFTWARE,
+        SOF_TIMESTAMPING_RX_SOFTWARE,
+        SOF_TIMESTAMPING_SOFTWARE,
+        SOF_TIMESTAMPING_OPT_ID,
+        SOF_TIMESTAMPING_OPT_TSONLY,
+        sigset_t,
+        SIGPIPE,
+        SIG_BLOCK,
+        SIG_SETMASK,
+
+        sigemptyset,
+        sigaddset,
+        sigismember,
+        sigpending,
+        sigtimedwait,
+        pthread_sigmask,
+        sendfile,
+        splice,
+        pipe2,
+        recvmsg,
+        sendmsg,
+        CMSG_FIRSTHDR,
+        CMSG_NXTHDR,
+        CMSG_DATA,
+        CMSG_SPACE,
+        CMSG_LEN
+    };
 }
 
 use self::libc::*;
@@ -124,9 +404,9 @@ use self::libc::*;
 macro_rules! impl_into_trait {
     ($($t:ty), +) => {
         $(
-            impl Into<c_int> for $t {
-                fn into(self) -> c_int {
-                    self as c_int
+            impl From<$t> for c_int {
+                fn from(value: $t) -> c_int {
+                    value as c_int
                 }
             }
         )+
@@ -174,12 +454,81 @@ pub mod Protocol {
     pub const ICMPv6: c_int = 58;
 }
 
-#[allow(non_snake_case)]
-///Possible flags for `accept4()`
-bitflags! (pub flags AcceptFlags: c_int {
-    const NON_BLOCKING    = SOCK_NONBLOCK,
-    const NON_INHERITABLE = SOCK_CLOEXEC,
-});
+bitflags! {
+    ///Possible flags for `accept4()`
+    pub struct AcceptFlags: c_int {
+        const NON_BLOCKING    = SOCK_NONBLOCK;
+        const NON_INHERITABLE = SOCK_CLOEXEC;
+    }
+}
+
+///Alias to `AcceptFlags::NON_BLOCKING`
+pub const NON_BLOCKING: AcceptFlags = AcceptFlags::NON_BLOCKING;
+///Alias to `AcceptFlags::NON_INHERITABLE`
+pub const NON_INHERITABLE: AcceptFlags = AcceptFlags::NON_INHERITABLE;
+
+bitflags! {
+    ///Flags for send and receive operations.
+    ///
+    ///Can be used in place of raw `c_int` flags.
+    pub struct MsgFlags: c_int {
+        ///Peeks at incoming data without removing it from queue.
+        const PEEK      = MSG_PEEK;
+        ///Waits until whole buffer is filled.
+        const WAITALL   = MSG_WAITALL;
+        ///Performs operation in non-blocking mode.
+        const DONTWAIT  = MSG_DONTWAIT;
+        ///Returns real length of datagram even if it was longer than buffer.
+        const TRUNC     = MSG_TRUNC;
+        ///Sends or receives out-of-band data.
+        const OOB       = MSG_OOB;
+        ///Bypasses routing and sends only to directly connected hosts.
+        const DONTROUTE = MSG_DONTROUTE;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        ///Doesn't raise `SIGPIPE` when peer closed connection.
+        const NOSIGNAL  = MSG_NOSIGNAL;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        ///Informs that more data is coming so that it can be coalesced.
+        const MORE      = MSG_MORE;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        ///Confirms that forward progress happened at link layer.
+        const CONFIRM   = MSG_CONFIRM;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        ///Receives queued errors from error queue.
+        const ERRQUEUE  = MSG_ERRQUEUE;
+        #[cfg(target_os = "linux")]
+        ///Sends data without copying it, see `SO_ZEROCOPY`.
+        const ZEROCOPY  = MSG_ZEROCOPY;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        ///Sends data in SYN packet using TCP Fast Open.
+        const FASTOPEN  = MSG_FASTOPEN;
+    }
+}
+
+impl From<MsgFlags> for c_int {
+    fn from(flags: MsgFlags) -> c_int {
+        flags.bits()
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+bitflags! {
+    ///Flags for `SO_TIMESTAMPING`.
+    ///
+    ///Available only on Linux.
+    pub struct Timestamping: c_uint {
+        ///Generates timestamp when data leaves kernel.
+        const TX_SOFTWARE = SOF_TIMESTAMPING_TX_SOFTWARE;
+        ///Generates timestamp when data enters kernel.
+        const RX_SOFTWARE = SOF_TIMESTAMPING_RX_SOFTWARE;
+        ///Reports generated software timestamps.
+        const SOFTWARE    = SOF_TIMESTAMPING_SOFTWARE;
+        ///Assigns identifier to each send to correlate TX timestamps with.
+        const OPT_ID      = SOF_TIMESTAMPING_OPT_ID;
+        ///Doesn't loop sent data back together with TX timestamps.
+        const OPT_TSONLY  = SOF_TIMESTAMPING_OPT_TSONLY;
+    }
+}
 
 #[repr(i32)]
 #[derive(Copy, Clone)]
@@ -196,21 +545,30 @@ pub enum ShutdownType {
 impl_into_trait!(ShutdownType);
 
 ///Raw socket
+///
+///Blocking calls that are interrupted by signal (`EINTR`) are restarted automatically.
 pub struct Socket {
-    inner: SOCKET
+    inner: SOCKET,
+    ///Whether zero linger timeout is set, so that `Drop` skips orderly shutdown.
+    abortive_close: AtomicBool,
+    ///Address family of socket, `AF_UNSPEC` until queried.
+    family: AtomicI32,
+    ///Whether writing to closed connection must not raise `SIGPIPE`.
+    nosigpipe: AtomicBool,
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Identifier of next `MSG_ZEROCOPY` send, mirrors kernel's `sk_zckey`.
+    zerocopy_id: AtomicU32
 }
 
 impl Socket {
     ///Initializes new socket.
     ///
     ///Corresponds to C connect()
-    pub fn new(family: c_int, _type: c_int, protocol: c_int) -> io::Result<Socket> {
+    pub fn new(family: c_int, _type: c_int, protocol: c_int) -> Result<Socket, SocketError> {
         unsafe {
             match socket(family, _type, protocol) {
-                SOCKET_ERROR => Err(io::Error::last_os_error()),
-                fd => Ok(Socket {
-                    inner: fd
-                }),
+                SOCKET_ERROR => Err(SocketError::last_os_error(Operation::Create)),
+                fd => Socket::from_raw_fd(fd).with_default_nosigpipe().operation(Operation::Create),
             }
         }
     }
@@ -227,36 +585,57 @@ impl Socket {
     ///Wraps `getsockname()`
     ///
     ///Available for binded/connected sockets.
-    pub fn name(&self) -> io::Result<net::SocketAddr> {
+    pub fn name(&self) -> Result<net::SocketAddr, SocketError> {
         unsafe {
             let mut storage: sockaddr_storage = mem::zeroed();
             let mut len = mem::size_of_val(&storage) as socklen_t;
 
             match getsockname(self.inner, &mut storage as *mut _ as *mut _, &mut len) {
-                SOCKET_ERROR => Err(io::Error::last_os_error()),
-                _ => sockaddr_to_addr(&storage, len)
+                SOCKET_ERROR => Err(SocketError::last_os_error(Operation::Name)),
+                _ => sockaddr_to_addr(&storage, len).operation(Operation::Name)
             }
         }
     }
 
+    ///Returns address family of socket.
+    ///
+    ///It is queried once with `getsockname()` and cached.
+    fn family(&self) -> c_int {
+        match self.family.load(Ordering::Relaxed) {
+            AF_UNSPEC => unsafe {
+                let mut storage: sockaddr_storage = mem::zeroed();
+                let mut len = mem::size_of_val(&storage) as socklen_t;
+
+                match getsockname(self.inner, &mut storage as *mut _ as *mut _, &mut len) {
+                    SOCKET_ERROR => AF_UNSPEC,
+                    _ => {
+                        self.family.store(storage.ss_family as c_int, Ordering::Relaxed);
+                        storage.ss_family as c_int
+                    }
+                }
+            },
+            family => family
+        }
+    }
+
     ///Binds socket to address.
-    pub fn bind(&self, addr: &net::SocketAddr) -> io::Result<()> {
-        let (addr, len) = get_raw_addr(addr);
+    pub fn bind(&self, addr: &net::SocketAddr) -> Result<(), SocketError> {
+        let (addr, len) = get_raw_addr(addr, self.family());
 
         unsafe {
-            match bind(self.inner, addr, len) {
+            match bind(self.inner, &addr as *const _ as *const _, len) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Bind))
             }
         }
     }
 
     ///Listens for incoming connections on this socket.
-    pub fn listen(&self, backlog: c_int) -> io::Result<()> {
+    pub fn listen(&self, backlog: c_int) -> Result<(), SocketError> {
         unsafe {
             match listen(self.inner, backlog) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Listen))
             }
         }
     }
@@ -264,44 +643,115 @@ impl Socket {
     ///Receives some bytes from socket
     ///
     ///Number of received bytes is returned on success
-    pub fn recv(&self, buf: &mut [u8], flags: c_int) -> io::Result<usize> {
+    pub fn recv<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<usize, SocketError> {
+        self.recv_into(as_uninit_mut(buf), flags.into()).operation(Operation::Recv)
+    }
+
+    ///Receives some bytes from socket into uninitialised buffer.
+    ///
+    ///Number of received bytes is returned on success.
+    ///Only that many leading bytes of `buf` are initialised.
+    ///
+    ///Returned number never exceeds length of `buf`, even with `MSG_TRUNC`.
+    pub fn recv_uninit<F: Into<c_int>>(&self, buf: &mut [MaybeUninit<u8>], flags: F) -> Result<usize, SocketError> {
         let len = buf.len();
+        self.recv_into(buf, flags.into()).map(|size| cmp::min(size, len)).operation(Operation::Recv)
+    }
 
-        unsafe {
+    //Returns number reported by system, which exceeds buffer length with `MSG_TRUNC`.
+    fn recv_into(&self, buf: &mut [MaybeUninit<u8>], flags: c_int) -> io::Result<usize> {
+        let len = buf.len();
+
+        retry_interrupted(|| unsafe {
             match recv(self.inner, buf.as_mut_ptr() as *mut c_void, len, flags) {
                 -1 => Err(io::Error::last_os_error()),
                 n => Ok(n as usize)
             }
-        }
+        })
     }
 
     ///Receives some bytes from socket
     ///
     ///Number of received bytes and remote address are returned on success.
-    pub fn recv_from(&self, buf: &mut [u8], flags: c_int) -> io::Result<(usize, net::SocketAddr)> {
+    ///
+    ///Excess bytes of datagram that doesn't fit into buffer are discarded.
+    pub fn recv_from<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<(usize, net::SocketAddr), SocketError> {
+        self.recv_from_into(as_uninit_mut(buf), flags.into()).operation(Operation::Recv)
+    }
+
+    ///Receives some bytes from socket into uninitialised buffer.
+    ///
+    ///Number of received bytes and remote address are returned on success.
+    ///Only that many leading bytes of `buf` are initialised.
+    ///
+    ///Returned number never exceeds length of `buf`, even with `MSG_TRUNC`.
+    pub fn recv_from_uninit<F: Into<c_int>>(&self, buf: &mut [MaybeUninit<u8>], flags: F) -> Result<(usize, net::SocketAddr), SocketError> {
         let len = buf.len();
+        self.recv_from_into(buf, flags.into()).map(|(size, addr)| (cmp::min(size, len), addr)).operation(Operation::Recv)
+    }
 
-        unsafe {
+    //Returns number reported by system, which exceeds buffer length with `MSG_TRUNC`.
+    fn recv_from_into(&self, buf: &mut [MaybeUninit<u8>], flags: c_int) -> io::Result<(usize, net::SocketAddr)> {
+        let len = buf.len();
+
+        retry_interrupted(|| unsafe {
             let mut storage: sockaddr_storage = mem::zeroed();
             let mut storage_len = mem::size_of_val(&storage) as socklen_t;
 
             match recvfrom(self.inner, buf.as_mut_ptr() as *mut c_void, len, flags, &mut storage as *mut _ as *mut _, &mut storage_len) {
                 -1 => Err(io::Error::last_os_error()),
                 n => {
-                    let peer_addr = sockaddr_to_addr(&storage, storage_len)?;
+                    let peer_addr = sockaddr_to_peer_addr(&storage, storage_len)?;
                     Ok((n as usize, peer_addr))
                 }
             }
-        }
+        })
+    }
+
+    ///Receives some bytes from socket without removing them from receive queue.
+    ///
+    ///Number of peeked bytes and remote address are returned on success.
+    ///Subsequent receive returns the same data.
+    pub fn peek_from(&self, buf: &mut [u8]) -> Result<(usize, net::SocketAddr), SocketError> {
+        self.recv_from(buf, MSG_PEEK)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns full size of next datagram without removing it from receive queue.
+    ///
+    ///Blocks until datagram arrives, unless socket is in non-blocking mode.
+    ///Zero is returned for empty datagram.
+    ///
+    ///Available only on Linux.
+    pub fn datagram_size(&self) -> Result<usize, SocketError> {
+        self.recv(&mut [], MSG_PEEK | MSG_TRUNC)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Receives datagram, reporting its full size even if it doesn't fit into buffer.
+    ///
+    ///Returns number of bytes written into buffer and full size of datagram.
+    ///Datagram is truncated if the latter is greater.
+    ///
+    ///Available only on Linux.
+    pub fn recv_truncating<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<(usize, usize), SocketError> {
+        let size = self.recv(buf, flags.into() | MSG_TRUNC)?;
+        Ok((cmp::min(size, buf.len()), size))
     }
 
     ///Sends some bytes through socket.
     ///
     ///Number of sent bytes is returned.
-    pub fn send(&self, buf: &[u8], flags: c_int) -> io::Result<usize> {
+    ///
+    ///If socket is shut down (`ESHUTDOWN`), zero is returned.
+    ///
+    ///Writing to closed connection fails with `BrokenPipe` instead of raising `SIGPIPE`,
+    ///unless it is allowed by `set_nosigpipe`.
+    pub fn send<F: Into<c_int>>(&self, buf: &[u8], flags: F) -> Result<usize, SocketError> {
+        let flags = self.send_flags(flags.into());
         let len = buf.len();
 
-        unsafe {
+        retry_interrupted(|| unsafe {
             match send(self.inner, buf.as_ptr() as *const c_void, len, flags) {
                 -1 => {
                     let error = io::Error::last_os_error();
@@ -316,7 +766,7 @@ impl Socket {
                 },
                 n => Ok(n as usize)
             }
-        }
+        }).operation(Operation::Send)
     }
 
     ///Sends some bytes through socket toward specified peer.
@@ -325,12 +775,15 @@ impl Socket {
     ///
     ///Note: the socket will be bound, if it isn't already.
     ///Use method `name` to determine address.
-    pub fn send_to(&self, buf: &[u8], peer_addr: &net::SocketAddr, flags: c_int) -> io::Result<usize> {
+    ///
+    ///Same as `send`, `SIGPIPE` is not raised unless allowed by `set_nosigpipe`.
+    pub fn send_to<F: Into<c_int>>(&self, buf: &[u8], peer_addr: &net::SocketAddr, flags: F) -> Result<usize, SocketError> {
+        let flags = self.send_flags(flags.into());
         let len = buf.len();
-        let (addr, addr_len) = get_raw_addr(peer_addr);
+        let (addr, addr_len) = get_raw_addr(peer_addr, self.family());
 
-        unsafe {
-            match sendto(self.inner, buf.as_ptr() as *const c_void, len, flags, addr, addr_len) {
+        retry_interrupted(|| unsafe {
+            match sendto(self.inner, buf.as_ptr() as *const c_void, len, flags, &addr as *const _ as *const _, addr_len) {
                 -1 => {
                     let error = io::Error::last_os_error();
                     let raw_code = error.raw_os_error().unwrap();
@@ -344,192 +797,1717 @@ impl Socket {
                 },
                 n => Ok(n as usize)
             }
-        }
+        }).operation(Operation::Send)
     }
 
-    ///Accept a new incoming client connection and return its files descriptor and address.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sends up to `len` bytes of file, starting at `offset`, without copying them into userspace.
     ///
-    ///By default the newly created socket will be inheritable by child processes and created
-    ///in blocking I/O mode. This behaviour can be customized using the `flags` parameter:
+    ///Wraps `sendfile`. File position is not changed.
     ///
-    /// * `AcceptFlags::NON_BLOCKING`    – Mark the newly created socket as non-blocking
-    /// * `AcceptFlags::NON_INHERITABLE` – Mark the newly created socket as not inheritable by client processes
+    ///Number of sent bytes is returned, which may be less than `len`.
+    ///Zero is returned if `offset` is at end of file.
+    ///In non-blocking mode `WouldBlock` is returned if nothing can be sent.
     ///
-    ///Depending on the operating system's availability of the `accept4(2)` system call this call
-    ///either pass the flags on to the operating system or emulate the call using `accept(2)`.
-    pub fn accept4(&self, flags: AcceptFlags) -> io::Result<(Socket, net::SocketAddr)> {
-        #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd", target_os = "dragonflybsd"))]
-        unsafe {
-            let mut storage: sockaddr_storage = mem::zeroed();
-            let mut len = mem::size_of_val(&storage) as socklen_t;
+    ///Same as `send`, `SIGPIPE` is not raised unless allowed by `set_nosigpipe`.
+    ///As `sendfile` has no flags, the signal is blocked for calling thread during the call instead.
+    ///
+    ///Available only on Linux.
+    pub fn send_file(&self, file: &fs::File, offset: u64, len: usize) -> Result<usize, SocketError> {
+        use std::os::unix::io::AsRawFd;
 
-            match accept4(self.inner, &mut storage as *mut _ as *mut _, &mut len, flags.bits()) {
-                SOCKET_ERROR => Err(io::Error::last_os_error()),
-                sock @ _ => {
-                    let addr = sockaddr_to_addr(&storage, len)?;
-                    Ok((Socket { inner: sock, }, addr))
-                }
-            }
+        if offset > off_t::MAX as u64 {
+            return Err(SocketError::new(Operation::Send, io::Error::new(io::ErrorKind::InvalidInput, "File offset is too large.")));
         }
 
-        #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd", target_os = "dragonflybsd")))]
-        {
-            self.accept().map(|(sock, addr)| {
-                // Emulate the two most common (and useful) `accept4` flags using `ioctl`/`fcntl`
-                //
-                // The only errors that can happen here fall into two categories:
-                //
-                //  * Programming errors on our side
-                //    (unlikely, but in this case panicking is actually the right thing to do anyway)
-                //  * Another thread causing havoc with random file descriptors
-                //    (always very bad and nothing, since there is absolutely nothing
-                //     that we OR USER can do about this)
-                sock.set_blocking(!flags.contains(NON_BLOCKING)).expect("Setting newly obtained client socket blocking mode");
-                sock.set_inheritable(!flags.contains(NON_INHERITABLE)).expect("Setting newly obtained client socket inheritance mode");
+        self.suppress_sigpipe(|| retry_interrupted(|| unsafe {
+            let mut offset = offset as off_t;
 
-                (sock, addr)
-            })
-        }
+            match sendfile(self.inner, file.as_raw_fd(), &mut offset, len) {
+                -1 => Err(io::Error::last_os_error()),
+                n => Ok(n as usize)
+            }
+        })).operation(Operation::Send)
     }
 
-
-    ///Accept a new incoming client connection and return its files descriptor and address.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Enables `MSG_ZEROCOPY` sends.
     ///
-    ///As this uses the classic `accept(2)` system call internally, you are **strongly advised** to
-    ///use the `.accept4()` method instead to get defined blocking and inheritance semantics for
-    ///the created file descriptor.
-    pub fn accept(&self) -> io::Result<(Socket, net::SocketAddr)> {
-        unsafe {
-            let mut storage: sockaddr_storage = mem::zeroed();
-            let mut len = mem::size_of_val(&storage) as socklen_t;
+    ///Wraps `SO_ZEROCOPY`. Without it `MSG_ZEROCOPY` is silently ignored.
+    ///
+    ///Available only on Linux.
+    pub fn enable_zerocopy(&self) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_ZEROCOPY, 1 as c_int)
+    }
 
-            match accept(self.inner, &mut storage as *mut _ as *mut _, &mut len) {
-                SOCKET_ERROR => Err(io::Error::last_os_error()),
-                sock @ _ => {
-                    let addr = sockaddr_to_addr(&storage, len)?;
-                    Ok((Socket { inner: sock }, addr))
-                }
-            }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sends some bytes through socket without copying them into kernel.
+    ///
+    ///Returns number of sent bytes and completion identifier of send.
+    ///Buffer must not be modified until completion is reported by `recv_extended_error`.
+    ///
+    ///Kernel numbers each successful zerocopy send of socket from zero and reports completions
+    ///as ranges of these numbers, see `ZerocopyCompletion`. Socket mirrors this counter, advancing it
+    ///only when non-empty send succeeds, so empty buffer is not sent at all and has no identifier.
+    ///Counter goes out of sync if sends are issued concurrently from several threads or
+    ///`MSG_ZEROCOPY` is passed to `send` directly.
+    ///Requires `enable_zerocopy`.
+    ///
+    ///Available only on Linux.
+    pub fn send_zerocopy<F: Into<c_int>>(&self, buf: &[u8], flags: F) -> Result<(usize, Option<u32>), SocketError> {
+        if buf.is_empty() {
+            return Ok((0, None));
         }
+
+        let len = self.send(buf, flags.into() | MSG_ZEROCOPY)?;
+        Ok((len, Some(self.zerocopy_id.fetch_add(1, Ordering::Relaxed))))
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether ICMP errors of IPv4 socket are queued into error queue.
+    ///
+    ///Wraps `IP_RECVERR`. Use `recv_error` to read them.
+    ///
+    ///Available only on Linux.
+    pub fn set_recv_error_v4(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_RECVERR, value as c_int)
+    }
 
-    ///Connects socket with remote address.
-    pub fn connect(&self, addr: &net::SocketAddr) -> io::Result<()> {
-        let (addr, len) = get_raw_addr(addr);
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns whether ICMP errors of IPv4 socket are queued into error queue.
+    ///
+    ///Available only on Linux.
+    pub fn recv_error_v4(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(IPPROTO_IP, IP_RECVERR).map(|value| value != 0)
+    }
 
-        unsafe {
-            match connect(self.inner, addr, len) {
-                0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
-            }
-        }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether ICMPv6 errors of IPv6 socket are queued into error queue.
+    ///
+    ///Wraps `IPV6_RECVERR`. Use `recv_error` to read them.
+    ///
+    ///Available only on Linux.
+    pub fn set_recv_error_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_RECVERR, value as c_int)
     }
 
-    ///Retrieves socket option.
-    pub fn get_opt<T>(&self, level: c_int, name: c_int) -> io::Result<T> {
-        unsafe {
-            let mut value: T = mem::zeroed();
-            let value_ptr = &mut value as *mut T as *mut c_void;
-            let mut value_len = mem::size_of::<T>() as socklen_t;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns whether ICMPv6 errors of IPv6 socket are queued into error queue.
+    ///
+    ///Available only on Linux.
+    pub fn recv_error_v6(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_RECVERR).map(|value| value != 0)
+    }
 
-            match getsockopt(self.inner, level, name, value_ptr, &mut value_len) {
-                0 => Ok(value),
-                _ => Err(io::Error::last_os_error())
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Reads one notification from socket's error queue together with packet that caused it.
+    ///
+    ///Wraps `recvmsg` with `MSG_ERRQUEUE`, which never blocks.
+    ///Returns `None` if queue is empty.
+    ///
+    ///Original packet is written into `buf`, e.g. payload of datagram that triggered ICMP error,
+    ///and its length is returned together with notification.
+    ///Excess bytes are discarded, in which case `truncated` is set (`MSG_TRUNC`).
+    ///
+    ///Available only on Linux.
+    pub fn recv_error(&self, buf: &mut [u8]) -> Result<Option<ErrorQueueMessage>, SocketError> {
+        let mut error = None;
+        let mut timestamp = None;
+
+        let result = self.recv_msg(as_uninit_mut(buf), MSG_ERRQUEUE, |level, kind, data| {
+            if (level == SOL_IP && kind == IP_RECVERR) || (level == SOL_IPV6 && kind == IPV6_RECVERR) {
+                error = ExtendedError::from_raw(data);
+            } else if level == SOL_SOCKET && kind == SCM_TIMESTAMPING {
+                timestamp = timespec_to_system_time(data);
             }
+        });
+
+        let (len, truncated) = match result {
+            Ok((len, _, msg_flags)) => (len, msg_flags & MSG_TRUNC != 0),
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+            Err(error) => return Err(error)
+        };
+
+        match error {
+            Some(mut error) => {
+                error.timestamp = timestamp;
+                Ok(Some(ErrorQueueMessage { len, truncated, error }))
+            },
+            //Message is already dequeued, so report it instead of silently skipping.
+            None => Err(SocketError::new(Operation::Recv, io::Error::new(io::ErrorKind::InvalidData, "Error queue message carries no extended error.")))
         }
     }
 
-    ///Sets socket option
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Reads one notification from socket's error queue, discarding packet that caused it.
     ///
-    ///Value is generally integer or C struct.
-    pub fn set_opt<T>(&self, level: c_int, name: c_int, value: T) -> io::Result<()> {
-        unsafe {
-            let value = &value as *const T as *const c_void;
-
-            match setsockopt(self.inner, level, name, value, mem::size_of::<T>() as socklen_t) {
-                0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
-            }
-        }
+    ///Same as `recv_error`, suitable for notifications without packet, such as completions.
+    ///
+    ///Available only on Linux.
+    pub fn recv_extended_error(&self) -> Result<Option<ExtendedError>, SocketError> {
+        self.recv_error(&mut []).map(|result| result.map(|message| message.error))
     }
 
-    ///Sets I/O parameters of socket.
-    pub fn ioctl(&self, request: IoctlRequestT, value: c_ulong) -> io::Result<()> {
-        unsafe {
-            let mut value = value;
-            let value = &mut value as *mut c_ulong;
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether received datagrams are timestamped with nanosecond precision.
+    ///
+    ///Wraps `SO_TIMESTAMPNS`. Use `recv_from_timestamped` to retrieve timestamps.
+    ///
+    ///Available only on Linux.
+    pub fn set_timestamp_ns(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_TIMESTAMPNS, value as c_int)
+    }
 
-            match ioctl(self.inner, request, value) {
-                0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
-            }
-        }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns whether received datagrams are timestamped with nanosecond precision.
+    ///
+    ///Available only on Linux.
+    pub fn timestamp_ns(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_TIMESTAMPNS).map(|value| value != 0)
     }
 
-    ///Sets non-blocking mode.
-    pub fn set_blocking(&self, value: bool) -> io::Result<()> {
-        self.ioctl(FIONBIO, (!value) as c_ulong)
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets timestamps generation and reporting.
+    ///
+    ///Wraps `SO_TIMESTAMPING`. RX timestamps are retrieved by `recv_from_timestamped`,
+    ///while TX timestamps are queued into error queue and read by `recv_extended_error`.
+    ///
+    ///Available only on Linux.
+    pub fn set_timestamping(&self, flags: Timestamping) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_TIMESTAMPING, flags.bits())
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns timestamps generation and reporting flags.
+    ///
+    ///Available only on Linux.
+    pub fn timestamping(&self) -> io::Result<Timestamping> {
+        self.get_opt_raw::<c_uint>(SOL_SOCKET, SO_TIMESTAMPING).map(Timestamping::from_bits_truncate)
+    }
 
-    ///Sets whether this socket will be inherited by newly created processes or not.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Receives some bytes from socket together with kernel's receive timestamp.
     ///
-    ///Internally this is implemented by calling `fcntl(fd, F_GETFD)` and `fcntl(fd, F_SETFD)`
-    ///to update the `FD_CLOEXEC` flag. (In the future this might use `ioctl(2)` on some
-    ///platforms instead.)
+    ///Number of received bytes, remote address and timestamp are returned on success.
+    ///Timestamp is present only if enabled by `set_timestamp_ns` or `set_timestamping`.
+    ///With `set_timestamping` datagrams that arrive right after enabling may lack timestamp,
+    ///as kernel starts generating them asynchronously.
     ///
-    ///This means that the socket will still be available to forked off child processes until it
-    ///calls `execve(2)` to complete the creation of a new process. A forking server application
-    ///(or similar) should therefore not expect this flag to have any effect on spawned off workers;
-    ///you're advised to manually call `.close()` on the socket instance in the worker process
-    ///instead. The standard library's `std::process` facility is not impacted by this however.
-    pub fn set_inheritable(&self, value: bool) -> io::Result<()> {
-        // Some (or possibly all?) OS's support the `FIOCLEX` and `FIONCLEX`
-        // `ioctl`s instead, however there is no support for that in `libc`
-        // currently and no usable documentation for figuring out who supports
-        // this feature online either
-        unsafe {
-            let mut flags: libc::c_int = libc::fcntl(self.inner, libc::F_GETFD);
-            if flags < 0 {
-                return Err(io::Error::last_os_error());
-            }
+    ///Available only on Linux.
+    pub fn recv_from_timestamped<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<(usize, net::SocketAddr, Option<SystemTime>), SocketError> {
+        let mut timestamp = None;
 
-            if value == true {
-                flags &= !libc::FD_CLOEXEC;
-            } else {
-                flags |= libc::FD_CLOEXEC;
+        let (len, addr, _) = self.recv_msg(as_uninit_mut(buf), flags.into(), |level, kind, data| {
+            if level == SOL_SOCKET && (kind == SCM_TIMESTAMPNS || kind == SCM_TIMESTAMPING) {
+                timestamp = timespec_to_system_time(data);
             }
+        })?;
 
-            if libc::fcntl(self.inner, libc::F_SETFD, flags) < 0 {
-                return Err(io::Error::last_os_error());
-            }
+        match addr {
+            Some(addr) => Ok((len, addr, timestamp)),
+            None => Err(SocketError::new(Operation::Recv, io::Error::new(io::ErrorKind::InvalidData, "Peer address is missing.")))
         }
-
-        Ok(())
     }
 
-    ///Returns whether this will be inherited by newly created processes or not.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether destination address and interface of received IPv4 datagrams are reported.
     ///
-    ///See `set_inheritable` for a detailed description of what this means.
-    pub fn get_inheritable(&self) -> io::Result<bool> {
-        unsafe {
-            let flags = libc::fcntl(self.inner, libc::F_GETFD);
-            if flags < 0 {
-                return Err(io::Error::last_os_error());
-            }
+    ///Wraps `IP_PKTINFO`. Use `recv_from_with_pktinfo` to retrieve them.
+    ///
+    ///Available only on Linux.
+    pub fn set_pktinfo_v4(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_PKTINFO, value as c_int)
+    }
 
-            Ok((flags & libc::FD_CLOEXEC) == 0)
-        }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns whether destination address and interface of received IPv4 datagrams are reported.
+    ///
+    ///Available only on Linux.
+    pub fn pktinfo_v4(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(IPPROTO_IP, IP_PKTINFO).map(|value| value != 0)
     }
 
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether destination address and interface of received IPv6 datagrams are reported.
+    ///
+    ///Wraps `IPV6_RECVPKTINFO`. Use `recv_from_with_pktinfo` to retrieve them.
+    ///
+    ///Available only on Linux.
+    pub fn set_pktinfo_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_RECVPKTINFO, value as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns whether destination address and interface of received IPv6 datagrams are reported.
+    ///
+    ///Available only on Linux.
+    pub fn pktinfo_v6(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_RECVPKTINFO).map(|value| value != 0)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Receives some bytes from socket together with their destination address and interface.
+    ///
+    ///Number of received bytes, remote address and packet info are returned on success.
+    ///Packet info is present only if enabled by `set_pktinfo_v4` or `set_pktinfo_v6`.
+    ///
+    ///Available only on Linux.
+    pub fn recv_from_with_pktinfo<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<(usize, net::SocketAddr, Option<PacketInfo>), SocketError> {
+        let mut info = None;
+
+        let (len, addr, _) = self.recv_msg(as_uninit_mut(buf), flags.into(), |level, kind, data| {
+            if level == SOL_IP && kind == IP_PKTINFO && data.len() >= mem::size_of::<in_pktinfo>() {
+                let raw: in_pktinfo = unsafe { ptr::read_unaligned(data.as_ptr() as *const in_pktinfo) };
+
+                info = Some(PacketInfo {
+                    addr: net::Ipv4Addr::from(u32::from_be(raw.ipi_addr.s_addr)).into(),
+                    interface: raw.ipi_ifindex as u32
+                });
+            } else if level == SOL_IPV6 && kind == IPV6_PKTINFO && data.len() >= mem::size_of::<in6_pktinfo>() {
+                let raw: in6_pktinfo = unsafe { ptr::read_unaligned(data.as_ptr() as *const in6_pktinfo) };
+
+                let addr = net::Ipv6Addr::from(raw.ipi6_addr.s6_addr);
+
+                //Same as peer address, IPv4-mapped address of dual-stack socket is unwrapped.
+                info = Some(PacketInfo {
+                    addr: addr.to_ipv4_mapped().map_or(net::IpAddr::V6(addr), net::IpAddr::V4),
+                    interface: raw.ipi6_ifindex as u32
+                });
+            }
+        })?;
+
+        match addr {
+            Some(addr) => Ok((len, addr, info)),
+            None => Err(SocketError::new(Operation::Recv, io::Error::new(io::ErrorKind::InvalidData, "Peer address is missing.")))
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sends some bytes through socket toward specified peer from specified source address.
+    ///
+    ///Source address and outgoing interface are taken from `info`, as returned by
+    ///`recv_from_with_pktinfo`, so that reply is sent from address request arrived on.
+    ///Zero interface lets routing decide.
+    ///
+    ///Number of sent bytes is returned.
+    ///
+    ///Available only on Linux.
+    pub fn send_to_with_pktinfo<F: Into<c_int>>(&self, buf: &[u8], peer_addr: &net::SocketAddr, info: &PacketInfo, flags: F) -> Result<usize, SocketError> {
+        let flags = flags.into();
+
+        match info.addr {
+            net::IpAddr::V4(addr) => {
+                let mut raw: in_pktinfo = unsafe { mem::zeroed() };
+                raw.ipi_ifindex = info.interface as _;
+                raw.ipi_spec_dst = in_addr { s_addr: u32::from(addr).to_be() };
+                let raw = unsafe { slice::from_raw_parts(&raw as *const in_pktinfo as *const u8, mem::size_of::<in_pktinfo>()) };
+
+                self.send_msg(buf, Some(peer_addr), SOL_IP, IP_PKTINFO, raw, flags)
+            },
+            net::IpAddr::V6(addr) => {
+                let mut raw: in6_pktinfo = unsafe { mem::zeroed() };
+                raw.ipi6_ifindex = info.interface as _;
+                raw.ipi6_addr = in6_addr { s6_addr: addr.octets() };
+                let raw = unsafe { slice::from_raw_parts(&raw as *const in6_pktinfo as *const u8, mem::size_of::<in6_pktinfo>()) };
+
+                self.send_msg(buf, Some(peer_addr), SOL_IPV6, IPV6_PKTINFO, raw, flags)
+            }
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets size of datagrams that kernel splits sent buffers into.
+    ///
+    ///Wraps `UDP_SEGMENT`. Last datagram may be shorter. `None` disables segmentation.
+    ///
+    ///Available only on Linux.
+    pub fn set_udp_segment(&self, size: Option<u16>) -> io::Result<()> {
+        self.set_opt_raw(SOL_UDP, UDP_SEGMENT, size.unwrap_or(0) as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns size of datagrams that kernel splits sent buffers into.
+    ///
+    ///Available only on Linux.
+    pub fn udp_segment(&self) -> io::Result<Option<u16>> {
+        self.get_opt_raw::<c_int>(SOL_UDP, UDP_SEGMENT).map(|size| match size {
+            0 => None,
+            size => Some(size as u16)
+        })
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sends buffer toward specified peer as datagrams of `segment_size` bytes.
+    ///
+    ///Segment size is passed as `UDP_SEGMENT` control message, overriding socket option.
+    ///Last datagram may be shorter.
+    ///
+    ///Number of sent bytes is returned.
+    ///
+    ///Available only on Linux.
+    pub fn send_to_segmented<F: Into<c_int>>(&self, buf: &[u8], peer_addr: &net::SocketAddr, segment_size: u16, flags: F) -> Result<usize, SocketError> {
+        self.send_msg(buf, Some(peer_addr), SOL_UDP, UDP_SEGMENT, &segment_size.to_ne_bytes(), flags.into())
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether datagrams of the same flow may be received coalesced into one buffer.
+    ///
+    ///Wraps `UDP_GRO`. Use `recv_from_gro` to split them back.
+    ///
+    ///Available only on Linux.
+    pub fn set_udp_gro(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(SOL_UDP, UDP_GRO, value as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns whether datagrams may be received coalesced.
+    ///
+    ///Available only on Linux.
+    pub fn udp_gro(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(SOL_UDP, UDP_GRO).map(|value| value != 0)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Receives datagrams that may be coalesced by `UDP_GRO`.
+    ///
+    ///Received datagrams and remote address are returned on success.
+    ///Datagrams are iterated as segments of received bytes, see `GroSegments`.
+    ///
+    ///Available only on Linux.
+    pub fn recv_from_gro<'a, F: Into<c_int>>(&self, buf: &'a mut [u8], flags: F) -> Result<(GroSegments<'a>, net::SocketAddr), SocketError> {
+        let mut segment_size = 0;
+
+        let (len, addr, msg_flags) = self.recv_msg(as_uninit_mut(buf), flags.into(), |level, kind, data| {
+            if level == SOL_UDP && kind == UDP_GRO && data.len() >= mem::size_of::<c_int>() {
+                segment_size = unsafe { ptr::read_unaligned(data.as_ptr() as *const c_int) } as usize;
+            }
+        })?;
+
+        let addr = match addr {
+            Some(addr) => addr,
+            None => return Err(SocketError::new(Operation::Recv, io::Error::new(io::ErrorKind::InvalidData, "Peer address is missing.")))
+        };
+        let buf: &'a [u8] = buf;
+        let data = &buf[..cmp::min(len, buf.len())];
+
+        Ok((GroSegments {
+            data,
+            rest: data,
+            //Missing or zero size means datagram wasn't coalesced.
+            segment_size: if segment_size == 0 { data.len() } else { segment_size },
+            truncated: msg_flags & MSG_TRUNC != 0,
+            done: false
+        }, addr))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sends message with single control message of `level` and `kind`.
+    fn send_msg(&self, buf: &[u8], addr: Option<&net::SocketAddr>, level: c_int, kind: c_int, data: &[u8], flags: c_int) -> Result<usize, SocketError> {
+        let flags = self.send_flags(flags);
+        let raw_addr = addr.map(|addr| get_raw_addr(addr, self.family()));
+        //Aligned for cmsghdr.
+        let mut control = [0u64; 8];
+        assert!(unsafe { CMSG_SPACE(data.len() as c_uint) } as usize <= mem::size_of_val(&control));
+
+        retry_interrupted(|| unsafe {
+            let mut iov = iovec {
+                iov_base: buf.as_ptr() as *mut c_void,
+                iov_len: buf.len()
+            };
+
+            let mut msg: msghdr = mem::zeroed();
+            if let Some((ref addr, addr_len)) = raw_addr {
+                msg.msg_name = addr as *const _ as *mut c_void;
+                msg.msg_namelen = addr_len;
+            }
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut c_void;
+            msg.msg_controllen = CMSG_SPACE(data.len() as c_uint) as _;
+
+            let cmsg = CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = level;
+            (*cmsg).cmsg_type = kind;
+            (*cmsg).cmsg_len = CMSG_LEN(data.len() as c_uint) as _;
+            ptr::copy_nonoverlapping(data.as_ptr(), CMSG_DATA(cmsg), data.len());
+
+            match sendmsg(self.inner, &msg, flags) {
+                -1 => Err(io::Error::last_os_error()),
+                n => Ok(n as usize)
+            }
+        }).operation(Operation::Send)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Receives message, passing each control message's level, type and data to `handler`.
+    ///
+    ///Returns number of received bytes, remote address and message flags.
+    fn recv_msg<H: FnMut(c_int, c_int, &[u8])>(&self, buf: &mut [MaybeUninit<u8>], flags: c_int, mut handler: H) -> Result<(usize, Option<net::SocketAddr>, c_int), SocketError> {
+        //Aligned for cmsghdr.
+        let mut control = [0u64; 64];
+
+        retry_interrupted(|| unsafe {
+            let mut storage: sockaddr_storage = mem::zeroed();
+            let mut iov = iovec {
+                iov_base: buf.as_mut_ptr() as *mut c_void,
+                iov_len: buf.len()
+            };
+
+            let mut msg: msghdr = mem::zeroed();
+            msg.msg_name = &mut storage as *mut _ as *mut c_void;
+            msg.msg_namelen = mem::size_of_val(&storage) as socklen_t;
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut c_void;
+            msg.msg_controllen = mem::size_of_val(&control) as _;
+
+            match recvmsg(self.inner, &mut msg, flags) {
+                -1 => Err(io::Error::last_os_error()),
+                n => {
+                    let mut cmsg = CMSG_FIRSTHDR(&msg);
+
+                    while !cmsg.is_null() {
+                        let data = CMSG_DATA(cmsg);
+                        let len = (*cmsg).cmsg_len as usize - (data as usize - cmsg as usize);
+                        handler((*cmsg).cmsg_level, (*cmsg).cmsg_type, slice::from_raw_parts(data, len));
+                        cmsg = CMSG_NXTHDR(&msg, cmsg);
+                    }
+
+                    Ok((n as usize, sockaddr_to_peer_addr(&storage, msg.msg_namelen).ok(), msg.msg_flags))
+                }
+            }
+        }).operation(Operation::Recv)
+    }
+
+    ///Accept a new incoming client connection and return its files descriptor and address.
+    ///
+    ///By default the newly created socket will be inheritable by child processes and created
+    ///in blocking I/O mode. This behaviour can be customized using the `flags` parameter:
+    ///
+    /// * `AcceptFlags::NON_BLOCKING`    – Mark the newly created socket as non-blocking
+    /// * `AcceptFlags::NON_INHERITABLE` – Mark the newly created socket as not inheritable by client processes
+    ///
+    ///Depending on the operating system's availability of the `accept4(2)` system call this call
+    ///either pass the flags on to the operating system or emulate the call using `accept(2)`.
+    pub fn accept4(&self, flags: AcceptFlags) -> Result<(Socket, net::SocketAddr), SocketError> {
+        #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd", target_os = "dragonfly"))]
+        {
+            retry_interrupted(|| unsafe {
+                let mut storage: sockaddr_storage = mem::zeroed();
+                let mut len = mem::size_of_val(&storage) as socklen_t;
+
+                match accept4(self.inner, &mut storage as *mut _ as *mut _, &mut len, flags.bits()) {
+                    SOCKET_ERROR => Err(io::Error::last_os_error()),
+                    sock => {
+                        let addr = sockaddr_to_peer_addr(&storage, len)?;
+                        Ok((Socket::from_raw_fd(sock), addr))
+                    }
+                }
+            }).operation(Operation::Accept)
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd", target_os = "dragonfly")))]
+        {
+            self.accept().map(|(sock, addr)| {
+                // Emulate the two most common (and useful) `accept4` flags using `ioctl`/`fcntl`
+                //
+                // The only errors that can happen here fall into two categories:
+                //
+                //  * Programming errors on our side
+                //    (unlikely, but in this case panicking is actually the right thing to do anyway)
+                //  * Another thread causing havoc with random file descriptors
+                //    (always very bad and nothing, since there is absolutely nothing
+                //     that we OR USER can do about this)
+                sock.set_blocking(!flags.contains(NON_BLOCKING)).expect("Setting newly obtained client socket blocking mode");
+                sock.set_inheritable(!flags.contains(NON_INHERITABLE)).expect("Setting newly obtained client socket inheritance mode");
+
+                (sock, addr)
+            })
+        }
+    }
+
+
+    ///Accept a new incoming client connection and return its files descriptor and address.
+    ///
+    ///As this uses the classic `accept(2)` system call internally, you are **strongly advised** to
+    ///use the `.accept4()` method instead to get defined blocking and inheritance semantics for
+    ///the created file descriptor.
+    pub fn accept(&self) -> Result<(Socket, net::SocketAddr), SocketError> {
+        retry_interrupted(|| unsafe {
+            let mut storage: sockaddr_storage = mem::zeroed();
+            let mut len = mem::size_of_val(&storage) as socklen_t;
+
+            match accept(self.inner, &mut storage as *mut _ as *mut _, &mut len) {
+                SOCKET_ERROR => Err(io::Error::last_os_error()),
+                sock => {
+                    let sock = Socket::from_raw_fd(sock).with_default_nosigpipe()?;
+                    let addr = sockaddr_to_peer_addr(&storage, len)?;
+                    Ok((sock, addr))
+                }
+            }
+        }).operation(Operation::Accept)
+    }
+
+
+    ///Connects socket with remote address.
+    ///
+    ///If blocking connect is interrupted by signal, it waits for connection to complete,
+    ///failing with `TimedOut` if it doesn't within send timeout (`SO_SNDTIMEO`).
+    pub fn connect(&self, addr: &net::SocketAddr) -> Result<(), SocketError> {
+        self.connect_raw(addr).operation(Operation::Connect)
+    }
+
+    fn connect_raw(&self, addr: &net::SocketAddr) -> io::Result<()> {
+        let (addr, len) = get_raw_addr(addr, self.family());
+
+        let result = unsafe {
+            match connect(self.inner, &addr as *const _ as *const _, len) {
+                0 => Ok(()),
+                _ => Err(io::Error::last_os_error())
+            }
+        };
+
+        match result {
+            //Interrupted connection is still being established so calling connect again
+            //would result in EALREADY. Instead wait for its completion.
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => {
+                let deadline = self.write_timeout()?.and_then(|timeout| Instant::now().checked_add(timeout));
+                self.wait_ready(false, deadline)?;
+
+                match self.take_error()? {
+                    Some(error) => Err(error),
+                    None => Ok(())
+                }
+            },
+            result => result
+        }
+    }
+
+    ///Starts connecting socket with remote address in non-blocking mode.
+    ///
+    ///Socket is switched into non-blocking mode and stays in it.
+    ///
+    ///`EINPROGRESS` and `EALREADY` are reported as `InProgress` as connection
+    ///continues asynchronously, while `EISCONN` is reported as `Connected`.
+    pub fn connect_nonblocking(&self, addr: &net::SocketAddr) -> Result<ConnectState<'_>, SocketError> {
+        self.set_blocking(false).operation(Operation::Ioctl)?;
+
+        match self.connect(addr) {
+            Ok(()) => Ok(ConnectState::Connected),
+            Err(error) => match error.raw_os_error() {
+                Some(code) if is_connect_in_progress(code) => Ok(ConnectState::InProgress(PendingConnect { socket: self })),
+                Some(code) if code == EISCONN => Ok(ConnectState::Connected),
+                _ => Err(error)
+            }
+        }
+    }
+
+    ///Connects socket with remote address, failing with `TimedOut` if it takes longer than `timeout`.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `O_NONBLOCK` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    ///
+    ///Note: on timeout connection attempt is not cancelled, so socket should be discarded.
+    pub fn connect_timeout(&self, addr: &net::SocketAddr, timeout: Duration) -> Result<(), SocketError> {
+        let deadline = deadline_after(timeout).operation(Operation::Connect)?;
+
+        self.with_nonblocking(|| match self.connect_nonblocking(addr).map_err(SocketError::into_inner)? {
+            ConnectState::Connected => Ok(()),
+            ConnectState::InProgress(pending) => loop {
+                self.wait_ready(false, deadline)?;
+
+                if pending.poll_complete_raw()? {
+                    return Ok(());
+                }
+            }
+        }).operation(Operation::Connect)
+    }
+
+    ///Receives some bytes from socket, failing with `TimedOut` if nothing arrives within `timeout`.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `O_NONBLOCK` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    pub fn recv_timeout<F: Into<c_int>>(&self, buf: &mut [u8], flags: F, timeout: Duration) -> Result<usize, SocketError> {
+        let flags = flags.into();
+        let deadline = deadline_after(timeout).operation(Operation::Recv)?;
+
+        self.with_nonblocking(|| loop {
+            match self.recv(buf, flags).map_err(SocketError::into_inner) {
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => self.wait_ready(true, deadline)?,
+                result => return result
+            }
+        }).operation(Operation::Recv)
+    }
+
+    ///Sends some bytes through socket, failing with `TimedOut` if it cannot proceed within `timeout`.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `O_NONBLOCK` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    pub fn send_timeout<F: Into<c_int>>(&self, buf: &[u8], flags: F, timeout: Duration) -> Result<usize, SocketError> {
+        let flags = flags.into();
+        let deadline = deadline_after(timeout).operation(Operation::Send)?;
+
+        self.with_nonblocking(|| loop {
+            match self.send(buf, flags).map_err(SocketError::into_inner) {
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => self.wait_ready(false, deadline)?,
+                result => return result
+            }
+        }).operation(Operation::Send)
+    }
+
+    ///Accepts incoming connection, failing with `TimedOut` if none arrives within `timeout`.
+    ///
+    ///Accepted socket is in blocking mode.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `O_NONBLOCK` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    pub fn accept_timeout(&self, timeout: Duration) -> Result<(Socket, net::SocketAddr), SocketError> {
+        let deadline = deadline_after(timeout).operation(Operation::Accept)?;
+
+        self.with_nonblocking(|| loop {
+            match self.accept4(AcceptFlags::empty()).map_err(SocketError::into_inner) {
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => self.wait_ready(true, deadline)?,
+                result => return result
+            }
+        }).operation(Operation::Accept)
+    }
+
+    ///Sends whole buffer through stream socket.
+    ///
+    ///Partial sends are continued until every byte is sent.
+    ///
+    ///If socket is in non-blocking mode, it waits for socket to become writable.
+    ///Whole call is limited by `timeout`, if it is specified, after which `TimedOut` is returned,
+    ///so that peer can't prolong it by accepting few bytes at a time.
+    ///Zero `timeout` disables waiting so that `WouldBlock` is returned instead.
+    ///
+    ///On error number of bytes that were sent is reported.
+    pub fn send_all<F: Into<c_int>>(&self, buf: &[u8], flags: F, timeout: Option<Duration>) -> Result<(), TransferError> {
+        let flags = flags.into();
+        let mut sent = 0;
+        //Unrepresentable deadline is as good as none.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+
+        while sent < buf.len() {
+            let result = match self.send(&buf[sent..], flags).map_err(SocketError::into_inner) {
+                Ok(0) => Err(io::Error::new(io::ErrorKind::WriteZero, "Failed to send whole buffer.")),
+                Ok(n) => {
+                    sent += n;
+                    Ok(())
+                },
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock && timeout != Some(Duration::from_secs(0)) => self.wait_ready(false, deadline),
+                Err(error) => Err(error)
+            };
+
+            if let Err(error) = result {
+                return Err(TransferError::new(sent, error));
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Receives exactly enough bytes from stream socket to fill buffer.
+    ///
+    ///Fails with `UnexpectedEof` if connection is closed before buffer is filled.
+    ///
+    ///If socket is in non-blocking mode, it waits for socket to become readable.
+    ///Whole call is limited by `timeout`, if it is specified, after which `TimedOut` is returned,
+    ///so that peer can't prolong it by sending few bytes at a time.
+    ///Zero `timeout` disables waiting so that `WouldBlock` is returned instead.
+    ///
+    ///On error number of bytes that were received into leading part of `buf` is reported.
+    ///`MSG_PEEK` must not be used as it would never make progress.
+    pub fn recv_exact<F: Into<c_int>>(&self, buf: &mut [u8], flags: F, timeout: Option<Duration>) -> Result<(), TransferError> {
+        let flags = flags.into();
+        let mut received = 0;
+        //Unrepresentable deadline is as good as none.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+
+        while received < buf.len() {
+            let result = match self.recv(&mut buf[received..], flags).map_err(SocketError::into_inner) {
+                Ok(0) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Failed to fill whole buffer.")),
+                Ok(n) => {
+                    received += n;
+                    Ok(())
+                },
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock && timeout != Some(Duration::from_secs(0)) => self.wait_ready(true, deadline),
+                Err(error) => Err(error)
+            };
+
+            if let Err(error) = result {
+                return Err(TransferError::new(received, error));
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Runs operation with socket in non-blocking mode, restoring original mode afterwards.
+    ///
+    ///Failure to restore mode is reported only if operation succeeded, whose result is dropped then.
+    fn with_nonblocking<T, F: FnOnce() -> io::Result<T>>(&self, op: F) -> io::Result<T> {
+        let is_blocking = self.is_blocking()?;
+
+        if is_blocking {
+            self.set_blocking(false)?;
+        }
+
+        let result = op();
+        let restored = if is_blocking { self.set_blocking(true) } else { Ok(()) };
+
+        //Failure of operation takes precedence over failure to restore mode.
+        result.and_then(|value| restored.map(|_| value))
+    }
+
+    ///Sets whether writing to closed connection must not raise `SIGPIPE`.
+    ///
+    ///Enabled by default, so that send operations fail with `BrokenPipe` instead.
+    ///
+    ///`MSG_NOSIGNAL` is passed to send operations where supported,
+    ///while on macOS and iOS `SO_NOSIGPIPE` is set.
+    ///
+    ///Sockets adopted by `from_raw_fd` are protected the same way as new ones, except on
+    ///macOS and iOS, where descriptor keeps its `SO_NOSIGPIPE` and `nosigpipe` reports it.
+    pub fn set_nosigpipe(&self, value: bool) -> io::Result<()> {
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        self.set_opt_raw(SOL_SOCKET, SO_NOSIGPIPE, value as c_int)?;
+
+        self.nosigpipe.store(value, Ordering::Relaxed);
+        Ok(())
+    }
+
+    ///Returns whether writing to closed connection must not raise `SIGPIPE`.
+    pub fn nosigpipe(&self) -> bool {
+        self.nosigpipe.load(Ordering::Relaxed)
+    }
+
+    ///Applies default `SIGPIPE` suppression to newly created socket.
+    fn with_default_nosigpipe(self) -> io::Result<Socket> {
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        self.set_opt_raw(SOL_SOCKET, SO_NOSIGPIPE, true as c_int)?;
+
+        Ok(self)
+    }
+
+    ///Adds `MSG_NOSIGNAL` to send flags, unless `SIGPIPE` is allowed.
+    fn send_flags(&self, flags: c_int) -> c_int {
+        #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+        {
+            if self.nosigpipe() {
+                return flags | MSG_NOSIGNAL;
+            }
+        }
+
+        flags
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Runs operation with `SIGPIPE` blocked for calling thread, unless it is allowed.
+    ///
+    ///Signal raised by operation is discarded, while already pending one is kept.
+    fn suppress_sigpipe<T, F: FnOnce() -> io::Result<T>>(&self, op: F) -> io::Result<T> {
+        if !self.nosigpipe() {
+            return op();
+        }
+
+        unsafe {
+            let mut pipe_set: sigset_t = mem::zeroed();
+            let mut pending: sigset_t = mem::zeroed();
+            let mut old_mask: sigset_t = mem::zeroed();
+            sigemptyset(&mut pipe_set);
+            sigaddset(&mut pipe_set, SIGPIPE);
+
+            let was_pending = sigpending(&mut pending) == 0 && sigismember(&pending, SIGPIPE) == 1;
+            match pthread_sigmask(SIG_BLOCK, &pipe_set, &mut old_mask) {
+                0 => (),
+                error => return Err(io::Error::from_raw_os_error(error))
+            }
+
+            let result = op();
+
+            if !was_pending {
+                match result {
+                    Err(ref error) if error.raw_os_error() == Some(EPIPE) => {
+                        let timeout = timespec { tv_sec: 0, tv_nsec: 0 };
+                        sigtimedwait(&pipe_set, ptr::null_mut(), &timeout);
+                    },
+                    _ => ()
+                }
+            }
+
+            pthread_sigmask(SIG_SETMASK, &old_mask, ptr::null_mut());
+            result
+        }
+    }
+
+    ///Waits until socket is ready for reading or writing, failing with `TimedOut` after deadline.
+    ///
+    ///Without deadline waits indefinitely.
+    fn wait_ready(&self, read: bool, deadline: Option<Instant>) -> io::Result<()> {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "Operation timed out."));
+                }
+
+                Some(duration_to_ms_ceil(deadline - now))
+            },
+            None => None
+        };
+
+        let result = if read {
+            select_raw(&[self], &[], &[self], timeout)?
+        } else {
+            select_raw(&[], &[self], &[self], timeout)?
+        };
+
+        match result {
+            0 => Err(io::Error::new(io::ErrorKind::TimedOut, "Operation timed out.")),
+            _ => Ok(())
+        }
+    }
+
+    ///Retrieves socket option.
+    pub fn get_opt<T>(&self, level: c_int, name: c_int) -> Result<T, SocketError> {
+        self.get_opt_raw(level, name).operation(Operation::GetOpt)
+    }
+
+    fn get_opt_raw<T>(&self, level: c_int, name: c_int) -> io::Result<T> {
+        unsafe {
+            let mut value: T = mem::zeroed();
+            let value_ptr = &mut value as *mut T as *mut c_void;
+            let mut value_len = mem::size_of::<T>() as socklen_t;
+
+            match getsockopt(self.inner, level, name, value_ptr, &mut value_len) {
+                0 => Ok(value),
+                _ => Err(io::Error::last_os_error())
+            }
+        }
+    }
+
+    ///Sets socket option
+    ///
+    ///Value is generally integer or C struct.
+    pub fn set_opt<T>(&self, level: c_int, name: c_int, value: T) -> Result<(), SocketError> {
+        self.set_opt_raw(level, name, value).operation(Operation::SetOpt)
+    }
+
+    fn set_opt_raw<T>(&self, level: c_int, name: c_int, value: T) -> io::Result<()> {
+        unsafe {
+            let value = &value as *const T as *const c_void;
+
+            match setsockopt(self.inner, level, name, value, mem::size_of::<T>() as socklen_t) {
+                0 => Ok(()),
+                _ => Err(io::Error::last_os_error())
+            }
+        }
+    }
+
+    ///Sets I/O parameters of socket.
+    pub fn ioctl(&self, request: IoctlRequestT, value: c_ulong) -> Result<(), SocketError> {
+        self.ioctl_raw(request, value).operation(Operation::Ioctl)
+    }
+
+    fn ioctl_raw(&self, request: IoctlRequestT, value: c_ulong) -> io::Result<()> {
+        let mut value = value;
+
+        unsafe {
+            self.ioctl_mut_raw(request, &mut value)
+        }
+    }
+
+    ///Controls I/O parameters of socket, passing pointer to `value`.
+    ///
+    ///Kernel may both read and write `value`, so it is used to retrieve output of request.
+    ///
+    ///# Safety
+    ///
+    ///`T` must be the type that `request` expects, as kernel accesses it without bounds checks.
+    pub unsafe fn ioctl_mut<T>(&self, request: IoctlRequestT, value: &mut T) -> Result<(), SocketError> {
+        self.ioctl_mut_raw(request, value).operation(Operation::Ioctl)
+    }
+
+    unsafe fn ioctl_mut_raw<T>(&self, request: IoctlRequestT, value: &mut T) -> io::Result<()> {
+        match ioctl(self.inner, request, value as *mut T as *mut c_void) {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error())
+        }
+    }
+
+    ///Returns number of bytes that can be received without blocking.
+    ///
+    ///Wraps `FIONREAD` (`SIOCINQ`).
+    ///For datagram sockets it is size of next datagram on Linux and Windows,
+    ///and total size of queued datagrams elsewhere.
+    pub fn bytes_available(&self) -> io::Result<usize> {
+        let mut value: c_int = 0;
+
+        unsafe {
+            self.ioctl_mut_raw(FIONREAD, &mut value)?;
+        }
+
+        Ok(value as usize)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns number of bytes in send queue that are not yet sent to peer.
+    ///
+    ///Wraps `SIOCOUTQNSD`. Supported only by stream sockets.
+    ///
+    ///Available only on Linux.
+    pub fn unsent_bytes(&self) -> io::Result<usize> {
+        let mut value: c_int = 0;
+
+        unsafe {
+            self.ioctl_mut_raw(SIOCOUTQNSD, &mut value)?;
+        }
+
+        Ok(value as usize)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns number of bytes sent to peer that are not yet acknowledged.
+    ///
+    ///Computed as difference between `SIOCOUTQ` and `SIOCOUTQNSD`, which are queried separately.
+    ///Supported only by stream sockets.
+    ///
+    ///Available only on Linux.
+    pub fn unacked_bytes(&self) -> io::Result<usize> {
+        let mut queued: c_int = 0;
+
+        unsafe {
+            self.ioctl_mut_raw(SIOCOUTQ, &mut queued)?;
+        }
+
+        Ok((queued as usize).saturating_sub(self.unsent_bytes()?))
+    }
+
+    ///Sets non-blocking mode.
+    pub fn set_blocking(&self, value: bool) -> io::Result<()> {
+        self.ioctl_raw(FIONBIO, (!value) as c_ulong)
+    }
+
+
+    ///Returns whether socket is in blocking mode.
+    fn is_blocking(&self) -> io::Result<bool> {
+        unsafe {
+            let flags = libc::fcntl(self.inner, libc::F_GETFL);
+            if flags < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok((flags & libc::O_NONBLOCK) == 0)
+        }
+    }
+
+    ///Sets whether this socket will be inherited by newly created processes or not.
+    ///
+    ///Internally this is implemented by calling `fcntl(fd, F_GETFD)` and `fcntl(fd, F_SETFD)`
+    ///to update the `FD_CLOEXEC` flag. (In the future this might use `ioctl(2)` on some
+    ///platforms instead.)
+    ///
+    ///This means that the socket will still be available to forked off child processes until it
+    ///calls `execve(2)` to complete the creation of a new process. A forking server application
+    ///(or similar) should therefore not expect this flag to have any effect on spawned off workers;
+    ///you're advised to manually call `.close()` on the socket instance in the worker process
+    ///instead. The standard library's `std::process` facility is not impacted by this however.
+    pub fn set_inheritable(&self, value: bool) -> io::Result<()> {
+        // Some (or possibly all?) OS's support the `FIOCLEX` and `FIONCLEX`
+        // `ioctl`s instead, however there is no support for that in `libc`
+        // currently and no usable documentation for figuring out who supports
+        // this feature online either
+        unsafe {
+            let mut flags: libc::c_int = libc::fcntl(self.inner, libc::F_GETFD);
+            if flags < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if value {
+                flags &= !libc::FD_CLOEXEC;
+            } else {
+                flags |= libc::FD_CLOEXEC;
+            }
+
+            if libc::fcntl(self.inner, libc::F_SETFD, flags) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Returns whether this will be inherited by newly created processes or not.
+    ///
+    ///See `set_inheritable` for a detailed description of what this means.
+    pub fn get_inheritable(&self) -> io::Result<bool> {
+        unsafe {
+            let flags = libc::fcntl(self.inner, libc::F_GETFD);
+            if flags < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok((flags & libc::FD_CLOEXEC) == 0)
+        }
+    }
+
+
+    ///Retrieves and clears pending socket error.
+    ///
+    ///Wraps `SO_ERROR`.
+    ///
+    ///For example, outcome of non-blocking `connect` is reported this way once socket becomes writable.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        match self.get_opt_raw::<c_int>(SOL_SOCKET, SO_ERROR)? {
+            0 => Ok(None),
+            error => Ok(Some(io::Error::from_raw_os_error(error)))
+        }
+    }
+
+    ///Sets timeout for receive operations.
+    ///
+    ///Wraps `SO_RCVTIMEO`. `None` means that operation blocks indefinitely.
+    ///
+    ///Zero duration is rejected with `InvalidInput` as kernel would treat it the same as `None`.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_RCVTIMEO, timeout_to_timeval(timeout)?)
+    }
+
+    ///Retrieves timeout for receive operations.
+    ///
+    ///Wraps `SO_RCVTIMEO`.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.get_opt_raw::<timeval>(SOL_SOCKET, SO_RCVTIMEO).map(timeval_to_timeout)
+    }
+
+    ///Sets timeout for send operations.
+    ///
+    ///Wraps `SO_SNDTIMEO`. `None` means that operation blocks indefinitely.
+    ///
+    ///Zero duration is rejected with `InvalidInput` as kernel would treat it the same as `None`.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_SNDTIMEO, timeout_to_timeval(timeout)?)
+    }
+
+    ///Retrieves timeout for send operations.
+    ///
+    ///Wraps `SO_SNDTIMEO`.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.get_opt_raw::<timeval>(SOL_SOCKET, SO_SNDTIMEO).map(timeval_to_timeout)
+    }
+
+    ///Sets size of receive buffer in bytes.
+    ///
+    ///Wraps `SO_RCVBUF`.
+    ///
+    ///Note: Linux doubles requested size to account for bookkeeping overhead,
+    ///clamps it by `net.core.rmem_max` and raises it to its minimum.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_RCVBUF, buffer_size_to_raw(size))
+    }
+
+    ///Retrieves size of receive buffer in bytes.
+    ///
+    ///Wraps `SO_RCVBUF`.
+    ///
+    ///Note: value is reported as is, so on Linux it is double the size passed
+    ///to `set_recv_buffer_size`, unless kernel clamped it.
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_RCVBUF).map(|size| size as usize)
+    }
+
+    ///Sets size of send buffer in bytes.
+    ///
+    ///Wraps `SO_SNDBUF`.
+    ///
+    ///Note: Linux doubles requested size to account for bookkeeping overhead,
+    ///clamps it by `net.core.wmem_max` and raises it to its minimum.
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_SNDBUF, buffer_size_to_raw(size))
+    }
+
+    ///Retrieves size of send buffer in bytes.
+    ///
+    ///Wraps `SO_SNDBUF`.
+    ///
+    ///Note: value is reported as is, so on Linux it is double the size passed
+    ///to `set_send_buffer_size`, unless kernel clamped it.
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_SNDBUF).map(|size| size as usize)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets size of receive buffer in bytes ignoring `net.core.rmem_max`.
+    ///
+    ///Wraps `SO_RCVBUFFORCE`. Requires `CAP_NET_ADMIN`.
+    ///
+    ///Use `recv_buffer_size` to read resulting value.
+    pub fn set_recv_buffer_size_force(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_RCVBUFFORCE, buffer_size_to_raw(size))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets size of send buffer in bytes ignoring `net.core.wmem_max`.
+    ///
+    ///Wraps `SO_SNDBUFFORCE`. Requires `CAP_NET_ADMIN`.
+    ///
+    ///Use `send_buffer_size` to read resulting value.
+    pub fn set_send_buffer_size_force(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_SNDBUFFORCE, buffer_size_to_raw(size))
+    }
+
+    ///Sets minimum number of bytes to be buffered before receive returns.
+    ///
+    ///Wraps `SO_RCVLOWAT`.
+    pub fn set_recv_low_water(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_RCVLOWAT, buffer_size_to_raw(size))
+    }
+
+    ///Retrieves minimum number of bytes to be buffered before receive returns.
+    ///
+    ///Wraps `SO_RCVLOWAT`.
+    pub fn recv_low_water(&self) -> io::Result<usize> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_RCVLOWAT).map(|size| size as usize)
+    }
+
+    ///Sets minimum number of bytes of free buffer space before send proceeds.
+    ///
+    ///Wraps `SO_SNDLOWAT`.
+    ///
+    ///Note: Linux doesn't allow to change it and fails with `ENOPROTOOPT`.
+    pub fn set_send_low_water(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_SNDLOWAT, buffer_size_to_raw(size))
+    }
+
+    ///Retrieves minimum number of bytes of free buffer space before send proceeds.
+    ///
+    ///Wraps `SO_SNDLOWAT`.
+    pub fn send_low_water(&self) -> io::Result<usize> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_SNDLOWAT).map(|size| size as usize)
+    }
+
+    ///Sets linger behaviour on close.
+    ///
+    ///Wraps `SO_LINGER`.
+    ///
+    ///When `Some`, close blocks until pending data is sent or timeout expires.
+    ///Timeout is measured in whole seconds and is rounded up.
+    ///
+    ///Zero timeout results in abortive close i.e. connection is reset instead of orderly shutdown.
+    pub fn set_linger(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let value = match timeout {
+            Some(timeout) => linger {
+                l_onoff: 1,
+                l_linger: cmp::min(timeout.as_secs() + (timeout.subsec_nanos() > 0) as u64, c_int::MAX as u64) as c_int
+            },
+            None => linger {
+                l_onoff: 0,
+                l_linger: 0
+            }
+        };
+
+        self.set_opt_raw(SOL_SOCKET, SO_LINGER, value)?;
+        self.abortive_close.store(value.l_onoff != 0 && value.l_linger == 0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    ///Retrieves linger behaviour on close.
+    ///
+    ///Wraps `SO_LINGER`.
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        self.get_opt_raw::<linger>(SOL_SOCKET, SO_LINGER).map(|value| match value.l_onoff {
+            0 => None,
+            _ => Some(Duration::from_secs(value.l_linger as u64))
+        })
+    }
+
+    ///Makes socket to be closed abortively.
+    ///
+    ///Shortcut for `set_linger(Some(Duration::from_secs(0)))`.
+    ///
+    ///On close or `Drop` peer receives `RST` instead of `FIN` and all pending data is discarded.
+    pub fn set_abortive_close(&self) -> io::Result<()> {
+        self.set_linger(Some(Duration::from_secs(0)))
+    }
+
+    ///Sets whether address can be re-used by `bind` while previous socket is lingering in `TIME_WAIT`.
+    ///
+    ///Wraps `SO_REUSEADDR`. It must be set before `bind`.
+    pub fn set_reuse_address(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_REUSEADDR, value as c_int)
+    }
+
+    ///Retrieves whether address can be re-used by `bind`.
+    ///
+    ///Wraps `SO_REUSEADDR`.
+    pub fn reuse_address(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_REUSEADDR).map(|value| value != 0)
+    }
+
+    ///Sets whether datagrams can be sent to broadcast addresses.
+    ///
+    ///Wraps `SO_BROADCAST`.
+    pub fn set_broadcast(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_BROADCAST, value as c_int)
+    }
+
+    ///Retrieves whether datagrams can be sent to broadcast addresses.
+    ///
+    ///Wraps `SO_BROADCAST`.
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_BROADCAST).map(|value| value != 0)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+    ///Sets whether multiple sockets are allowed to bind to the same address and port.
+    ///
+    ///Wraps `SO_REUSEPORT`. It must be set on every socket before `bind`.
+    pub fn set_reuse_port(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(SOL_SOCKET, SO_REUSEPORT, value as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+    ///Retrieves whether multiple sockets are allowed to bind to the same address and port.
+    ///
+    ///Wraps `SO_REUSEPORT`.
+    pub fn reuse_port(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(SOL_SOCKET, SO_REUSEPORT).map(|value| value != 0)
+    }
+
+    ///Sets whether IPv6 socket is restricted to IPv6 communication only.
+    ///
+    ///Wraps `IPV6_V6ONLY`. It must be set before `bind`.
+    ///
+    ///When disabled, socket also accepts IPv4 traffic using IPv4-mapped addresses.
+    pub fn set_only_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_V6ONLY, value as c_int)
+    }
+
+    ///Retrieves whether IPv6 socket is restricted to IPv6 communication only.
+    ///
+    ///Wraps `IPV6_V6ONLY`.
+    pub fn only_v6(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_V6ONLY).map(|value| value != 0)
+    }
+
+    ///Binds IPv6 socket to all interfaces for both IPv6 and IPv4 traffic.
+    ///
+    ///Disables `IPV6_V6ONLY` and binds to `[::]:port`.
+    ///
+    ///IPv4 peers are reported by `accept` and `recv_from` as plain IPv4 addresses
+    ///instead of IPv4-mapped ones.
+    pub fn bind_dual_stack(&self, port: u16) -> Result<(), SocketError> {
+        self.set_only_v6(false).operation(Operation::SetOpt)?;
+        self.bind(&net::SocketAddr::V6(net::SocketAddrV6::new(net::Ipv6Addr::UNSPECIFIED, port, 0, 0)))
+    }
+
+    ///Sets time-to-live of outgoing IPv4 packets.
+    ///
+    ///Wraps `IP_TTL`.
+    pub fn set_ttl(&self, ttl: u8) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_TTL, ttl as c_int)
+    }
+
+    ///Returns time-to-live of outgoing IPv4 packets.
+    pub fn ttl(&self) -> io::Result<u8> {
+        self.get_opt_raw::<c_int>(IPPROTO_IP, IP_TTL).map(|ttl| ttl as u8)
+    }
+
+    ///Sets hop limit of outgoing unicast IPv6 packets.
+    ///
+    ///Wraps `IPV6_UNICAST_HOPS`.
+    pub fn set_unicast_hops_v6(&self, hops: u8) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_UNICAST_HOPS, hops as c_int)
+    }
+
+    ///Returns hop limit of outgoing unicast IPv6 packets.
+    pub fn unicast_hops_v6(&self) -> io::Result<u8> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_UNICAST_HOPS).map(|hops| hops as u8)
+    }
+
+    ///Sets type of service field of outgoing IPv4 packets.
+    ///
+    ///Wraps `IP_TOS`. Kernel may refuse to set ECN bits on stream sockets.
+    pub fn set_tos(&self, tos: TrafficClass) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_TOS, tos.raw() as c_int)
+    }
+
+    ///Returns type of service field of outgoing IPv4 packets.
+    pub fn tos(&self) -> io::Result<TrafficClass> {
+        self.get_opt_raw::<c_int>(IPPROTO_IP, IP_TOS).map(|tos| TrafficClass::from_raw(tos as u8))
+    }
+
+    ///Sets traffic class field of outgoing IPv6 packets.
+    ///
+    ///Wraps `IPV6_TCLASS`.
+    pub fn set_tclass_v6(&self, tclass: TrafficClass) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_TCLASS, tclass.raw() as c_int)
+    }
+
+    ///Returns traffic class field of outgoing IPv6 packets.
+    pub fn tclass_v6(&self) -> io::Result<TrafficClass> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_TCLASS).map(|tclass| TrafficClass::from_raw(tclass as u8))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether time-to-live of received IPv4 packets is reported.
+    ///
+    ///Wraps `IP_RECVTTL`. Use `recv_from_with_header` to retrieve it.
+    ///
+    ///Available only on Linux.
+    pub fn set_recv_ttl(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_RECVTTL, value as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether type of service field of received IPv4 packets is reported.
+    ///
+    ///Wraps `IP_RECVTOS`. Use `recv_from_with_header` to retrieve it.
+    ///
+    ///Available only on Linux.
+    pub fn set_recv_tos(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_RECVTOS, value as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether hop limit of received IPv6 packets is reported.
+    ///
+    ///Wraps `IPV6_RECVHOPLIMIT`. Use `recv_from_with_header` to retrieve it.
+    ///
+    ///Available only on Linux.
+    pub fn set_recv_hop_limit_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_RECVHOPLIMIT, value as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets whether traffic class field of received IPv6 packets is reported.
+    ///
+    ///Wraps `IPV6_RECVTCLASS`. Use `recv_from_with_header` to retrieve it.
+    ///
+    ///Available only on Linux.
+    pub fn set_recv_tclass_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_RECVTCLASS, value as c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Receives some bytes from socket together with fields of IP header.
+    ///
+    ///Number of received bytes, remote address and header fields are returned on success.
+    ///Fields are present only if enabled by `set_recv_ttl`, `set_recv_tos`,
+    ///`set_recv_hop_limit_v6` or `set_recv_tclass_v6`.
+    ///
+    ///Available only on Linux.
+    pub fn recv_from_with_header<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<(usize, net::SocketAddr, IpHeader), SocketError> {
+        let mut header = IpHeader::default();
+
+        let (len, addr, _) = self.recv_msg(as_uninit_mut(buf), flags.into(), |level, kind, data| {
+            let int = || if data.len() >= mem::size_of::<c_int>() {
+                Some(unsafe { ptr::read_unaligned(data.as_ptr() as *const c_int) } as u8)
+            } else {
+                None
+            };
+
+            match (level, kind) {
+                (SOL_IP, IP_TTL) => header.hop_limit = int(),
+                //Single byte, unlike the rest.
+                (SOL_IP, IP_TOS) => header.traffic_class = data.first().map(|tos| TrafficClass::from_raw(*tos)),
+                (SOL_IPV6, IPV6_HOPLIMIT) => header.hop_limit = int(),
+                (SOL_IPV6, IPV6_TCLASS) => header.traffic_class = int().map(TrafficClass::from_raw),
+                _ => ()
+            }
+        })?;
+
+        match addr {
+            Some(addr) => Ok((len, addr, header)),
+            None => Err(SocketError::new(Operation::Recv, io::Error::new(io::ErrorKind::InvalidData, "Peer address is missing.")))
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets path MTU discovery mode of IPv4 socket.
+    ///
+    ///Wraps `IP_MTU_DISCOVER`.
+    ///
+    ///Available only on Linux.
+    pub fn set_mtu_discover_v4(&self, mode: PmtuDiscovery) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_MTU_DISCOVER, c_int::from(mode))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns path MTU discovery mode of IPv4 socket.
+    ///
+    ///Available only on Linux.
+    pub fn mtu_discover_v4(&self) -> io::Result<PmtuDiscovery> {
+        self.get_opt_raw::<c_int>(IPPROTO_IP, IP_MTU_DISCOVER).and_then(PmtuDiscovery::from_raw)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Sets path MTU discovery mode of IPv6 socket.
+    ///
+    ///Wraps `IPV6_MTU_DISCOVER`.
+    ///
+    ///Available only on Linux.
+    pub fn set_mtu_discover_v6(&self, mode: PmtuDiscovery) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_MTU_DISCOVER, c_int::from(mode))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns path MTU discovery mode of IPv6 socket.
+    ///
+    ///Available only on Linux.
+    pub fn mtu_discover_v6(&self) -> io::Result<PmtuDiscovery> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_MTU_DISCOVER).and_then(PmtuDiscovery::from_raw)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns current path MTU of connected IPv4 socket.
+    ///
+    ///Wraps `IP_MTU`. Fails with `NotConnected` if socket is not connected.
+    ///
+    ///Available only on Linux.
+    pub fn path_mtu_v4(&self) -> io::Result<u32> {
+        self.get_opt_raw::<c_int>(IPPROTO_IP, IP_MTU).map(|mtu| mtu as u32)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Returns current path MTU of connected IPv6 socket.
+    ///
+    ///Wraps `IPV6_MTU`. Fails with `NotConnected` if socket is not connected.
+    ///
+    ///Available only on Linux.
+    pub fn path_mtu_v6(&self) -> io::Result<u32> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_MTU).map(|mtu| mtu as u32)
+    }
+
+    ///Joins IPv4 multicast group on interface with specified address.
+    ///
+    ///Wraps `IP_ADD_MEMBERSHIP`. Unspecified address lets kernel choose interface.
+    pub fn join_multicast_v4(&self, group: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = ip_mreq {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_interface: ipv4_to_raw(interface)
+        };
+
+        self.set_opt_raw(IPPROTO_IP, IP_ADD_MEMBERSHIP, mreq)
+    }
+
+    ///Leaves IPv4 multicast group joined by `join_multicast_v4`.
+    ///
+    ///Wraps `IP_DROP_MEMBERSHIP`.
+    pub fn leave_multicast_v4(&self, group: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = ip_mreq {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_interface: ipv4_to_raw(interface)
+        };
+
+        self.set_opt_raw(IPPROTO_IP, IP_DROP_MEMBERSHIP, mreq)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    ///Joins IPv4 multicast group on interface with specified index.
+    ///
+    ///Wraps `IP_ADD_MEMBERSHIP` with `ip_mreqn`. Zero index lets kernel choose interface.
+    ///
+    ///Available only on Linux and FreeBSD.
+    pub fn join_multicast_v4_index(&self, group: &net::Ipv4Addr, interface: u32) -> io::Result<()> {
+        let mreq = ip_mreqn {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_address: ipv4_to_raw(&net::Ipv4Addr::UNSPECIFIED),
+            imr_ifindex: interface as c_int
+        };
+
+        self.set_opt_raw(IPPROTO_IP, IP_ADD_MEMBERSHIP, mreq)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    ///Leaves IPv4 multicast group joined by `join_multicast_v4_index`.
+    ///
+    ///Available only on Linux and FreeBSD.
+    pub fn leave_multicast_v4_index(&self, group: &net::Ipv4Addr, interface: u32) -> io::Result<()> {
+        let mreq = ip_mreqn {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_address: ipv4_to_raw(&net::Ipv4Addr::UNSPECIFIED),
+            imr_ifindex: interface as c_int
+        };
+
+        self.set_opt_raw(IPPROTO_IP, IP_DROP_MEMBERSHIP, mreq)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+    ///Joins IPv4 multicast group, receiving only datagrams sent from `source`.
+    ///
+    ///Wraps `IP_ADD_SOURCE_MEMBERSHIP`. Interface is specified by its address,
+    ///unspecified address lets kernel choose interface.
+    ///
+    ///Available only on Linux, macOS and FreeBSD.
+    pub fn join_source_multicast_v4(&self, group: &net::Ipv4Addr, source: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = ip_mreq_source {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_interface: ipv4_to_raw(interface),
+            imr_sourceaddr: ipv4_to_raw(source)
+        };
+
+        self.set_opt_raw(IPPROTO_IP, IP_ADD_SOURCE_MEMBERSHIP, mreq)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+    ///Leaves IPv4 source-specific multicast group joined by `join_source_multicast_v4`.
+    ///
+    ///Available only on Linux, macOS and FreeBSD.
+    pub fn leave_source_multicast_v4(&self, group: &net::Ipv4Addr, source: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = ip_mreq_source {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_interface: ipv4_to_raw(interface),
+            imr_sourceaddr: ipv4_to_raw(source)
+        };
+
+        self.set_opt_raw(IPPROTO_IP, IP_DROP_SOURCE_MEMBERSHIP, mreq)
+    }
+
+    ///Joins IPv6 multicast group on interface with specified index.
+    ///
+    ///Wraps `IPV6_JOIN_GROUP`. Zero index lets kernel choose interface.
+    pub fn join_multicast_v6(&self, group: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        let mreq = ipv6_mreq {
+            ipv6mr_multiaddr: in6_addr { s6_addr: group.octets() },
+            ipv6mr_interface: interface as _
+        };
+
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_JOIN_GROUP, mreq)
+    }
+
+    ///Leaves IPv6 multicast group joined by `join_multicast_v6`.
+    ///
+    ///Wraps `IPV6_LEAVE_GROUP`.
+    pub fn leave_multicast_v6(&self, group: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        let mreq = ipv6_mreq {
+            ipv6mr_multiaddr: in6_addr { s6_addr: group.octets() },
+            ipv6mr_interface: interface as _
+        };
+
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_LEAVE_GROUP, mreq)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+    ///Joins IPv6 multicast group, receiving only datagrams sent from `source`.
+    ///
+    ///Wraps `MCAST_JOIN_SOURCE_GROUP`. Zero index lets kernel choose interface.
+    ///
+    ///Available only on Linux, macOS and FreeBSD.
+    pub fn join_source_multicast_v6(&self, group: &net::Ipv6Addr, source: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, MCAST_JOIN_SOURCE_GROUP, group_source_req_v6(group, source, interface))
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+    ///Leaves IPv6 source-specific multicast group joined by `join_source_multicast_v6`.
+    ///
+    ///Wraps `MCAST_LEAVE_SOURCE_GROUP`.
+    ///
+    ///Available only on Linux, macOS and FreeBSD.
+    pub fn leave_source_multicast_v6(&self, group: &net::Ipv6Addr, source: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, MCAST_LEAVE_SOURCE_GROUP, group_source_req_v6(group, source, interface))
+    }
+
+    ///Sets address of interface to send IPv4 multicast datagrams from.
+    ///
+    ///Wraps `IP_MULTICAST_IF`. Unspecified address restores default.
+    pub fn set_multicast_if_v4(&self, interface: &net::Ipv4Addr) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_MULTICAST_IF, ipv4_to_raw(interface))
+    }
+
+    ///Returns address of interface to send IPv4 multicast datagrams from.
+    pub fn multicast_if_v4(&self) -> io::Result<net::Ipv4Addr> {
+        self.get_opt_raw::<in_addr>(IPPROTO_IP, IP_MULTICAST_IF).map(|addr| net::Ipv4Addr::from(u32::from_be(addr.s_addr)))
+    }
+
+    ///Sets index of interface to send IPv6 multicast datagrams from.
+    ///
+    ///Wraps `IPV6_MULTICAST_IF`. Zero index restores default.
+    pub fn set_multicast_if_v6(&self, interface: u32) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_MULTICAST_IF, interface as c_int)
+    }
+
+    ///Returns index of interface to send IPv6 multicast datagrams from.
+    pub fn multicast_if_v6(&self) -> io::Result<u32> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_MULTICAST_IF).map(|interface| interface as u32)
+    }
+
+    ///Sets time-to-live of outgoing IPv4 multicast datagrams.
+    ///
+    ///Wraps `IP_MULTICAST_TTL`. Default of one keeps datagrams within local network.
+    pub fn set_multicast_ttl_v4(&self, ttl: u8) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_MULTICAST_TTL, ttl)
+    }
+
+    ///Returns time-to-live of outgoing IPv4 multicast datagrams.
+    pub fn multicast_ttl_v4(&self) -> io::Result<u8> {
+        self.get_opt_raw::<u8>(IPPROTO_IP, IP_MULTICAST_TTL)
+    }
+
+    ///Sets hop limit of outgoing IPv6 multicast datagrams.
+    ///
+    ///Wraps `IPV6_MULTICAST_HOPS`.
+    pub fn set_multicast_hops_v6(&self, hops: u8) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_MULTICAST_HOPS, hops as c_int)
+    }
+
+    ///Returns hop limit of outgoing IPv6 multicast datagrams.
+    pub fn multicast_hops_v6(&self) -> io::Result<u8> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_MULTICAST_HOPS).map(|hops| hops as u8)
+    }
+
+    ///Sets whether sent IPv4 multicast datagrams are looped back to local sockets.
+    ///
+    ///Wraps `IP_MULTICAST_LOOP`.
+    pub fn set_multicast_loop_v4(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IP, IP_MULTICAST_LOOP, value as u8)
+    }
+
+    ///Returns whether sent IPv4 multicast datagrams are looped back to local sockets.
+    pub fn multicast_loop_v4(&self) -> io::Result<bool> {
+        self.get_opt_raw::<u8>(IPPROTO_IP, IP_MULTICAST_LOOP).map(|value| value != 0)
+    }
+
+    ///Sets whether sent IPv6 multicast datagrams are looped back to local sockets.
+    ///
+    ///Wraps `IPV6_MULTICAST_LOOP`.
+    pub fn set_multicast_loop_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(IPPROTO_IPV6, IPV6_MULTICAST_LOOP, value as c_int)
+    }
+
+    ///Returns whether sent IPv6 multicast datagrams are looped back to local sockets.
+    pub fn multicast_loop_v6(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(IPPROTO_IPV6, IPV6_MULTICAST_LOOP).map(|value| value != 0)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    ///Attaches classic BPF program that selects socket within `SO_REUSEPORT` group.
+    ///
+    ///Wraps `SO_ATTACH_REUSEPORT_CBPF`.
+    ///
+    ///Program returns index of socket in the group, in order sockets joined it.
+    ///If index is out of bounds, kernel falls back to hash based selection.
+    ///Program attached to any socket applies to the whole group.
+    pub fn attach_reuse_port_cbpf(&self, program: &[sock_filter]) -> io::Result<()> {
+        let program = sock_fprog {
+            len: program.len() as _,
+            filter: program.as_ptr() as *mut _
+        };
+
+        self.set_opt_raw(SOL_SOCKET, SO_ATTACH_REUSEPORT_CBPF, program)
+    }
 
     ///Stops receive and/or send over socket.
-    pub fn shutdown(&self, direction: ShutdownType) -> io::Result<()> {
+    pub fn shutdown(&self, direction: ShutdownType) -> Result<(), SocketError> {
         unsafe {
             match shutdown(self.inner, direction.into()) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Shutdown))
             }
         }
     }
@@ -538,25 +2516,200 @@ impl Socket {
     ///
     ///Note: on `Drop` socket will be closed on its own.
     ///There is no need to close it explicitly.
-    pub fn close(&self) -> io::Result<()> {
+    pub fn close(&self) -> Result<(), SocketError> {
         unsafe {
             match close(self.inner) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Close))
+            }
+        }
+    }
+}
+
+///Classifies OS error code.
+pub(crate) fn classify_os_error(code: c_int) -> ErrorClass {
+    const TRANSIENT: &[c_int] = &[EAGAIN, EWOULDBLOCK, EINTR, ENOBUFS, ENOMEM, EINPROGRESS, EALREADY, ETIMEDOUT];
+    const PEER_CLOSED: &[c_int] = &[ECONNRESET, ECONNABORTED, ECONNREFUSED, EPIPE, ENOTCONN, ENETRESET, SOCKET_SHUTDOWN];
+    const ADDRESS: &[c_int] = &[EADDRINUSE, EADDRNOTAVAIL, EAFNOSUPPORT, ENETUNREACH, EHOSTUNREACH, ENETDOWN, EHOSTDOWN, EDESTADDRREQ];
+    const PERMISSION: &[c_int] = &[EACCES, EPERM];
+    const PROGRAMMER: &[c_int] = &[EBADF, ENOTSOCK, EINVAL, EFAULT, EISCONN, EOPNOTSUPP, ENOPROTOOPT, EPROTONOSUPPORT, EPROTOTYPE, ESOCKTNOSUPPORT, EMSGSIZE];
+
+    if TRANSIENT.contains(&code) {
+        ErrorClass::Transient
+    } else if PEER_CLOSED.contains(&code) {
+        ErrorClass::PeerClosed
+    } else if ADDRESS.contains(&code) {
+        ErrorClass::Address
+    } else if PERMISSION.contains(&code) {
+        ErrorClass::Permission
+    } else if PROGRAMMER.contains(&code) {
+        ErrorClass::Programmer
+    } else {
+        ErrorClass::Other
+    }
+}
+
+///Views initialised buffer as uninitialised one.
+#[inline]
+fn as_uninit_mut(buf: &mut [u8]) -> &mut [MaybeUninit<u8>] {
+    //Safe as `MaybeUninit<u8>` has same layout and callee never writes uninitialised bytes.
+    unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) }
+}
+
+///Retries operation while it is interrupted by signal.
+#[inline]
+fn retry_interrupted<T, F: FnMut() -> io::Result<T>>(mut op: F) -> io::Result<T> {
+    loop {
+        match op() {
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result
+        }
+    }
+}
+
+#[inline]
+fn is_connect_in_progress(code: c_int) -> bool {
+    code == EINPROGRESS || code == EALREADY
+}
+
+///Outcome of `Socket::connect_nonblocking`.
+pub enum ConnectState<'a> {
+    ///Connection is established.
+    Connected,
+    ///Connection is being established.
+    InProgress(PendingConnect<'a>)
+}
+
+///Connection that is being established in non-blocking mode.
+pub struct PendingConnect<'a> {
+    socket: &'a Socket
+}
+
+impl<'a> PendingConnect<'a> {
+    ///Returns socket that is being connected.
+    pub fn socket(&self) -> &'a Socket {
+        self.socket
+    }
+
+    ///Checks whether connection is complete without blocking.
+    ///
+    ///Returns `true` once connection is established and `false` while it is still in progress.
+    ///
+    ///Failure to connect is reported as error taken from `SO_ERROR`.
+    ///As it is cleared once taken, subsequent polls report `NotConnected` error.
+    pub fn poll_complete(&self) -> Result<bool, SocketError> {
+        self.poll_complete_raw().operation(Operation::Connect)
+    }
+
+    fn poll_complete_raw(&self) -> io::Result<bool> {
+        match select_raw(&[], &[self.socket], &[self.socket], Some(0))? {
+            0 => Ok(false),
+            _ => match self.socket.take_error()? {
+                Some(error) => Err(error),
+                //Connection is confirmed by presence of peer.
+                None => unsafe {
+                    let mut storage: sockaddr_storage = mem::zeroed();
+                    let mut len = mem::size_of_val(&storage) as socklen_t;
+
+                    match getpeername(self.socket.inner, &mut storage as *mut _ as *mut _, &mut len) {
+                        SOCKET_ERROR => Err(io::Error::last_os_error()),
+                        _ => Ok(true)
+                    }
+                }
             }
         }
     }
 }
 
-fn get_raw_addr(addr: &net::SocketAddr) -> (*const sockaddr, socklen_t) {
-    match *addr {
+#[inline]
+fn ipv4_to_raw(addr: &net::Ipv4Addr) -> in_addr {
+    in_addr { s_addr: u32::from(*addr).to_be() }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+fn group_source_req_v6(group: &net::Ipv6Addr, source: &net::Ipv6Addr, interface: u32) -> group_source_req {
+    let group = net::SocketAddr::V6(net::SocketAddrV6::new(*group, 0, 0, 0));
+    let source = net::SocketAddr::V6(net::SocketAddrV6::new(*source, 0, 0, 0));
+
+    group_source_req {
+        gsr_interface: interface,
+        gsr_group: get_raw_addr(&group, AF_INET6).0,
+        gsr_source: get_raw_addr(&source, AF_INET6).0
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[inline]
+fn ifa_broadaddr(ifa: &ifaddrs) -> *mut sockaddr {
+    ifa.ifa_ifu
+}
+
+#[cfg(any(target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+#[inline]
+fn ifa_broadaddr(ifa: &ifaddrs) -> *mut sockaddr {
+    ifa.ifa_dstaddr
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+///Reads IPv4 address from possibly null `sockaddr`.
+unsafe fn sockaddr_to_ipv4(addr: *const sockaddr) -> Option<net::Ipv4Addr> {
+    match addr.as_ref() {
+        Some(raw) if raw.sa_family as c_int == AF_INET => {
+            let raw = &*(addr as *const sockaddr_in);
+            Some(net::Ipv4Addr::from(u32::from_be(raw.sin_addr.s_addr)))
+        },
+        _ => None
+    }
+}
+
+///Converts address to raw one suitable for socket of specified `family`.
+///
+///IPv4 addresses are converted to IPv4-mapped ones for `AF_INET6` sockets, so that
+///peers reported by dual-stack sockets can be passed back to them.
+fn get_raw_addr(addr: &net::SocketAddr, family: c_int) -> (sockaddr_storage, socklen_t) {
+    //Layout of std's addresses is not guaranteed to match C structs so we fill storage by hand.
+    let mut storage: sockaddr_storage = unsafe { mem::zeroed() };
+
+    let mapped;
+    let addr = match *addr {
+        net::SocketAddr::V4(ref a) if family == AF_INET6 => {
+            mapped = net::SocketAddr::V6(net::SocketAddrV6::new(a.ip().to_ipv6_mapped(), a.port(), 0, 0));
+            &mapped
+        },
+        _ => addr
+    };
+
+    let len = match *addr {
         net::SocketAddr::V4(ref a) => {
-            (a as *const _ as *const _, mem::size_of_val(a) as socklen_t)
+            let raw = unsafe { &mut *(&mut storage as *mut _ as *mut sockaddr_in) };
+            //BSD kernels validate length stored in address itself, e.g. within `group_source_req`.
+            #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+            {
+                raw.sin_len = mem::size_of::<sockaddr_in>() as u8;
+            }
+            raw.sin_family = AF_INET as sa_family_t;
+            raw.sin_port = a.port().to_be();
+            raw.sin_addr = in_addr { s_addr: u32::from(*a.ip()).to_be() };
+
+            mem::size_of::<sockaddr_in>()
         }
         net::SocketAddr::V6(ref a) => {
-            (a as *const _ as *const _, mem::size_of_val(a) as socklen_t)
+            let raw = unsafe { &mut *(&mut storage as *mut _ as *mut sockaddr_in6) };
+            #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+            {
+                raw.sin6_len = mem::size_of::<sockaddr_in6>() as u8;
+            }
+            raw.sin6_family = AF_INET6 as sa_family_t;
+            raw.sin6_port = a.port().to_be();
+            raw.sin6_flowinfo = a.flowinfo();
+            raw.sin6_addr = in6_addr { s6_addr: a.ip().octets() };
+            raw.sin6_scope_id = a.scope_id();
+
+            mem::size_of::<sockaddr_in6>()
         }
-    }
+    };
+
+    (storage, len as socklen_t)
 }
 
 fn sockaddr_to_addr(storage: &sockaddr_storage, len: socklen_t) -> io::Result<net::SocketAddr> {
@@ -574,7 +2727,7 @@ fn sockaddr_to_addr(storage: &sockaddr_storage, len: socklen_t) -> io::Result<ne
         AF_INET6 => {
             assert!(len as usize >= mem::size_of::<sockaddr_in6>());
             let storage = unsafe { *(storage as *const _ as *const sockaddr_in6) };
-            let ip = net::Ipv6Addr::from(storage.sin6_addr.s6_addr.clone());
+            let ip = net::Ipv6Addr::from(storage.sin6_addr.s6_addr);
 
             Ok(net::SocketAddr::V6(net::SocketAddrV6::new(ip, storage.sin6_port.to_be(), storage.sin6_flowinfo, storage.sin6_scope_id)))
         }
@@ -584,9 +2737,23 @@ fn sockaddr_to_addr(storage: &sockaddr_storage, len: socklen_t) -> io::Result<ne
     }
 }
 
+///Converts address of peer, unwrapping IPv4-mapped addresses of dual-stack sockets.
+fn sockaddr_to_peer_addr(storage: &sockaddr_storage, len: socklen_t) -> io::Result<net::SocketAddr> {
+    match sockaddr_to_addr(storage, len)? {
+        net::SocketAddr::V6(addr) => match addr.ip().to_ipv4_mapped() {
+            Some(ip) => Ok(net::SocketAddr::V4(net::SocketAddrV4::new(ip, addr.port()))),
+            None => Ok(net::SocketAddr::V6(addr))
+        },
+        addr => Ok(addr)
+    }
+}
+
 impl Drop for Socket {
     fn drop(&mut self) {
-        let _ = self.shutdown(ShutdownType::Both);
+        //Orderly shutdown would send FIN before reset so skip it for abortive close.
+        if !self.abortive_close.load(Ordering::Relaxed) {
+            let _ = self.shutdown(ShutdownType::Both);
+        }
         let _ = self.close();
     }
 }
@@ -605,7 +2772,23 @@ impl AsRawFd for Socket {
 
 impl FromRawFd for Socket {
     unsafe fn from_raw_fd(sock: SOCKET) -> Self {
-        Socket {inner: sock}
+        let socket = Socket {
+            inner: sock,
+            abortive_close: AtomicBool::new(false),
+            family: AtomicI32::new(AF_UNSPEC),
+            nosigpipe: AtomicBool::new(true),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            zerocopy_id: AtomicU32::new(0)
+        };
+
+        //Adopted descriptor keeps its SO_NOSIGPIPE, so report what is in effect.
+        #[cfg(any(target_os = "macos", target_os = "ios"))]
+        {
+            let value = socket.get_opt_raw::<c_int>(SOL_SOCKET, SO_NOSIGPIPE).map(|value| value != 0).unwrap_or(false);
+            socket.nosigpipe.store(value, Ordering::Relaxed);
+        }
+
+        socket
     }
 }
 
@@ -617,14 +2800,718 @@ impl IntoRawFd for Socket {
     }
 }
 
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Policy of selecting socket within `SO_REUSEPORT` group.
+pub enum ReusePortSteering {
+    ///Socket is selected by index of CPU that processes packet.
+    ///
+    ///Works best when number of sockets is equal to number of CPUs and
+    ///each worker thread is pinned to its CPU.
+    Cpu,
+    ///Socket is selected by source port of packet modulo size of group.
+    ///
+    ///Note: IPv6 extension headers are not supported.
+    SourcePort,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl ReusePortSteering {
+    ///Creates classic BPF program for group of specified size and family.
+    ///
+    ///Only `Family::IPv4` and `Family::IPv6` are supported, others are rejected with `InvalidInput`.
+    ///Size must be within `1..=u32::MAX`, otherwise it is rejected with `InvalidInput` too.
+    pub fn program(&self, family: c_int, size: usize) -> io::Result<Vec<sock_filter>> {
+        #[inline]
+        fn stmt(code: u32, k: u32) -> sock_filter {
+            sock_filter {
+                code: code as u16,
+                jt: 0,
+                jf: 0,
+                k
+            }
+        }
+
+        if family != AF_INET && family != AF_INET6 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Steering is supported only for IPv4 and IPv6."));
+        }
+
+        let size = match size {
+            0 => return Err(io::Error::new(io::ErrorKind::InvalidInput, "Group cannot be empty.")),
+            size if size as u64 > u32::MAX as u64 => return Err(io::Error::new(io::ErrorKind::InvalidInput, "Group is too large.")),
+            size => size as u32
+        };
+
+        let mut program = match *self {
+            ReusePortSteering::Cpu => vec![
+                stmt(BPF_LD | BPF_W | BPF_ABS, (SKF_AD_OFF + SKF_AD_CPU) as u32),
+            ],
+            //Offsets are relative to network header as L4 header is already pulled
+            ReusePortSteering::SourcePort => match family {
+                AF_INET => vec![
+                    stmt(BPF_LD | BPF_B | BPF_ABS, SKF_NET_OFF as u32),
+                    stmt(BPF_ALU | BPF_AND | BPF_K, 0xf),
+                    stmt(BPF_ALU | BPF_LSH | BPF_K, 2),
+                    stmt(BPF_MISC | BPF_TAX, 0),
+                    stmt(BPF_LD | BPF_H | BPF_IND, SKF_NET_OFF as u32),
+                ],
+                _ => vec![
+                    stmt(BPF_LD | BPF_H | BPF_ABS, (SKF_NET_OFF + 40) as u32),
+                ]
+            }
+        };
+
+        program.push(stmt(BPF_ALU | BPF_MOD | BPF_K, size));
+        program.push(stmt(BPF_RET | BPF_A, 0));
+        Ok(program)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+///Creates group of sockets bound to the same address with `SO_REUSEPORT`.
+///
+///If port of `addr` is zero, then all sockets are bound to port assigned to the first one.
+///
+///When `steering` is specified, corresponding classic BPF program is attached to the group
+///so that incoming traffic is distributed deterministically.
+///
+///Note: for `Type::STREAM` sockets group membership is established by `listen`,
+///so it must be called in order of returned sockets.
+pub fn reuse_port_group(family: c_int, _type: c_int, protocol: c_int, addr: &net::SocketAddr, size: usize, steering: Option<ReusePortSteering>) -> Result<Vec<Socket>, SocketError> {
+    if size == 0 {
+        return Err(SocketError::new(Operation::Create, io::Error::new(io::ErrorKind::InvalidInput, "Group cannot be empty.")));
+    }
+
+    let program = match steering {
+        Some(steering) => Some(steering.program(family, size).operation(Operation::Create)?),
+        None => None
+    };
+
+    let mut addr = *addr;
+    let mut group = Vec::with_capacity(size);
+
+    for _ in 0..size {
+        let socket = Socket::new(family, _type, protocol)?;
+        socket.set_reuse_port(true).operation(Operation::SetOpt)?;
+        socket.bind(&addr)?;
+
+        if addr.port() == 0 {
+            addr.set_port(socket.name()?.port());
+        }
+
+        group.push(socket);
+    }
+
+    if let Some(program) = program {
+        group[0].attach_reuse_port_cbpf(&program).operation(Operation::SetOpt)?;
+    }
+
+    Ok(group)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+///IPv4 broadcast address of network interface.
+pub struct InterfaceBroadcast {
+    ///Name of interface.
+    pub name: String,
+    ///Address assigned to interface.
+    pub addr: net::Ipv4Addr,
+    ///Broadcast address of the subnet.
+    pub broadcast: net::Ipv4Addr
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly"))]
+///Lists IPv4 broadcast addresses of network interfaces.
+///
+///Wraps `getifaddrs`. Only addresses of interfaces with `IFF_BROADCAST` flag are listed,
+///so loopback and point-to-point interfaces are omitted.
+///Interface with multiple IPv4 addresses is listed once for each of them.
+pub fn interface_broadcasts() -> io::Result<Vec<InterfaceBroadcast>> {
+    let mut list: *mut ifaddrs = ptr::null_mut();
+
+    unsafe {
+        if getifaddrs(&mut list) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut entry = list;
+
+    while let Some(ifa) = unsafe { entry.as_ref() } {
+        entry = ifa.ifa_next;
+
+        if ifa.ifa_flags & IFF_BROADCAST as c_uint == 0 {
+            continue;
+        }
+
+        let (addr, broadcast) = unsafe {
+            match (sockaddr_to_ipv4(ifa.ifa_addr), sockaddr_to_ipv4(ifa_broadaddr(ifa))) {
+                (Some(addr), Some(broadcast)) => (addr, broadcast),
+                _ => continue
+            }
+        };
+
+        let name = unsafe { CStr::from_ptr(ifa.ifa_name) };
+        result.push(InterfaceBroadcast {
+            name: name.to_string_lossy().into_owned(),
+            addr,
+            broadcast
+        });
+    }
+
+    unsafe {
+        freeifaddrs(list);
+    }
+
+    Ok(result)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Path MTU discovery mode.
+///
+///Available only on Linux.
+pub enum PmtuDiscovery {
+    ///Never sets Don't Fragment flag, fragmenting packets as needed.
+    Dont = IP_PMTUDISC_DONT,
+    ///Discovers path MTU, fragmenting packets that exceed it.
+    Want = IP_PMTUDISC_WANT,
+    ///Discovers path MTU, failing with `EMSGSIZE` on packets that exceed it.
+    Do = IP_PMTUDISC_DO,
+    ///Sets Don't Fragment flag, but ignores path MTU, to probe it.
+    Probe = IP_PMTUDISC_PROBE,
+    ///Uses interface MTU, ignoring path MTU and ICMP errors.
+    Interface = IP_PMTUDISC_INTERFACE
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl_into_trait!(PmtuDiscovery);
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl PmtuDiscovery {
+    fn from_raw(mode: c_int) -> io::Result<PmtuDiscovery> {
+        match mode {
+            IP_PMTUDISC_DONT => Ok(PmtuDiscovery::Dont),
+            IP_PMTUDISC_WANT => Ok(PmtuDiscovery::Want),
+            IP_PMTUDISC_DO => Ok(PmtuDiscovery::Do),
+            IP_PMTUDISC_PROBE => Ok(PmtuDiscovery::Probe),
+            IP_PMTUDISC_INTERFACE => Ok(PmtuDiscovery::Interface),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown path MTU discovery mode."))
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Explicit Congestion Notification codepoint.
+pub enum Ecn {
+    ///Transport is not ECN capable.
+    NotEct = 0,
+    ///ECN capable transport, `ECT(1)`.
+    Ect1 = 1,
+    ///ECN capable transport, `ECT(0)`.
+    Ect0 = 2,
+    ///Congestion experienced.
+    Ce = 3
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+///IPv4 type of service or IPv6 traffic class field.
+///
+///Consists of DSCP in upper six bits and ECN in lower two bits.
+pub struct TrafficClass(u8);
+
+impl TrafficClass {
+    ///Creates field from DSCP, of which only lower six bits are used, and ECN.
+    pub fn new(dscp: u8, ecn: Ecn) -> TrafficClass {
+        TrafficClass((dscp << 2) | ecn as u8)
+    }
+
+    ///Creates field from its raw value.
+    pub fn from_raw(raw: u8) -> TrafficClass {
+        TrafficClass(raw)
+    }
+
+    ///Returns raw value of field.
+    pub fn raw(&self) -> u8 {
+        self.0
+    }
+
+    ///Returns Differentiated Services Code Point.
+    pub fn dscp(&self) -> u8 {
+        self.0 >> 2
+    }
+
+    ///Returns Explicit Congestion Notification codepoint.
+    pub fn ecn(&self) -> Ecn {
+        match self.0 & 0b11 {
+            0 => Ecn::NotEct,
+            1 => Ecn::Ect1,
+            2 => Ecn::Ect0,
+            _ => Ecn::Ce
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+///Fields of IP header of received packet.
+///
+///Available only on Linux.
+pub struct IpHeader {
+    ///IPv4 time-to-live or IPv6 hop limit.
+    pub hop_limit: Option<u8>,
+    ///IPv4 type of service or IPv6 traffic class.
+    pub traffic_class: Option<TrafficClass>
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+///Kernel pipe to move data between sockets with `splice`.
+///
+///Data never enters userspace.
+///Bytes that destination couldn't accept remain in pipe and are sent first on next transfer.
+///
+///Available only on Linux.
+pub struct SplicePipe {
+    read: c_int,
+    write: c_int,
+    pending: usize
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl SplicePipe {
+    ///Creates new pipe.
+    pub fn new() -> io::Result<SplicePipe> {
+        let mut fds: [c_int; 2] = [0; 2];
+
+        unsafe {
+            match pipe2(fds.as_mut_ptr(), O_CLOEXEC) {
+                0 => Ok(SplicePipe {
+                    read: fds[0],
+                    write: fds[1],
+                    pending: 0
+                }),
+                _ => Err(io::Error::last_os_error())
+            }
+        }
+    }
+
+    ///Returns number of bytes held in pipe, that are not yet sent to destination.
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    ///Moves up to `len` bytes from `from` to `to`.
+    ///
+    ///Number of bytes delivered to `to` is returned.
+    ///Zero is returned once `from` reaches end of stream and pipe is empty.
+    ///
+    ///If `to` would block after accepting part of data, the rest is kept in pipe and
+    ///partial count is returned. `WouldBlock` is returned only if no progress was made,
+    ///which makes it suitable for non-blocking sockets.
+    ///
+    ///Same as `send`, `SIGPIPE` is not raised unless allowed by `set_nosigpipe` of `to`.
+    pub fn transfer(&mut self, from: &Socket, to: &Socket, len: usize) -> io::Result<usize> {
+        if self.pending == 0 {
+            self.pending = splice_fds(from.inner, self.write, len)?;
+
+            if self.pending == 0 {
+                return Ok(0);
+            }
+        }
+
+        let mut sent = 0;
+
+        while self.pending > 0 {
+            match to.suppress_sigpipe(|| splice_fds(self.read, to.inner, self.pending)) {
+                Ok(n) => {
+                    self.pending -= n;
+                    sent += n;
+                },
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock && sent > 0 => break,
+                Err(error) => return Err(error)
+            }
+        }
+
+        Ok(sent)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl Drop for SplicePipe {
+    fn drop(&mut self) {
+        unsafe {
+            close(self.read);
+            close(self.write);
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn splice_fds(from: c_int, to: c_int, len: usize) -> io::Result<usize> {
+    retry_interrupted(|| unsafe {
+        match splice(from, ptr::null_mut(), to, ptr::null_mut(), len, SPLICE_F_MOVE) {
+            -1 => Err(io::Error::last_os_error()),
+            n => Ok(n as usize)
+        }
+    })
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Origin of extended error.
+pub enum ErrorOrigin {
+    ///No origin.
+    None,
+    ///Local error, e.g. message exceeds path MTU.
+    Local,
+    ///ICMP message.
+    Icmp,
+    ///ICMPv6 message.
+    Icmp6,
+    ///Transmit status, e.g. timestamp (`SO_EE_ORIGIN_TIMESTAMPING`).
+    TxStatus,
+    ///Zerocopy completion.
+    Zerocopy,
+    ///Unknown origin.
+    Other(u8)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl From<u8> for ErrorOrigin {
+    fn from(origin: u8) -> ErrorOrigin {
+        match origin {
+            SO_EE_ORIGIN_NONE => ErrorOrigin::None,
+            SO_EE_ORIGIN_LOCAL => ErrorOrigin::Local,
+            SO_EE_ORIGIN_ICMP => ErrorOrigin::Icmp,
+            SO_EE_ORIGIN_ICMP6 => ErrorOrigin::Icmp6,
+            SO_EE_ORIGIN_TXSTATUS => ErrorOrigin::TxStatus,
+            SO_EE_ORIGIN_ZEROCOPY => ErrorOrigin::Zerocopy,
+            origin => ErrorOrigin::Other(origin)
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+///Message read from socket's error queue by `recv_error`.
+///
+///Available only on Linux.
+pub struct ErrorQueueMessage {
+    ///Number of bytes of original packet written into buffer.
+    pub len: usize,
+    ///Whether original packet didn't fit into buffer and was truncated.
+    pub truncated: bool,
+    ///Notification itself.
+    pub error: ExtendedError
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+///Notification from socket's error queue.
+///
+///Decoded `sock_extended_err` with address of node that reported it.
+///
+///Available only on Linux.
+pub struct ExtendedError {
+    errno: u32,
+    origin: ErrorOrigin,
+    kind: u8,
+    code: u8,
+    info: u32,
+    data: u32,
+    offender: Option<net::SocketAddr>,
+    timestamp: Option<SystemTime>
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl ExtendedError {
+    fn from_raw(data: &[u8]) -> Option<ExtendedError> {
+        let size = mem::size_of::<sock_extended_err>();
+
+        if data.len() < size {
+            return None;
+        }
+
+        let raw: sock_extended_err = unsafe { ptr::read_unaligned(data.as_ptr() as *const sock_extended_err) };
+
+        //Offender address follows structure, its family is AF_UNSPEC if there is none.
+        let offender = &data[size..];
+        let offender = unsafe {
+            let mut storage: sockaddr_storage = mem::zeroed();
+            let len = cmp::min(offender.len(), mem::size_of_val(&storage));
+            ptr::copy_nonoverlapping(offender.as_ptr(), &mut storage as *mut _ as *mut u8, len);
+            sockaddr_to_peer_addr(&storage, len as socklen_t).ok()
+        };
+
+        Some(ExtendedError {
+            errno: raw.ee_errno,
+            origin: raw.ee_origin.into(),
+            kind: raw.ee_type,
+            code: raw.ee_code,
+            info: raw.ee_info,
+            data: raw.ee_data,
+            offender,
+            timestamp: None
+        })
+    }
+
+    ///Returns error number, zero if notification is not error.
+    pub fn errno(&self) -> u32 {
+        self.errno
+    }
+
+    ///Returns error, if any.
+    pub fn error(&self) -> Option<io::Error> {
+        match self.errno {
+            0 => None,
+            errno => Some(io::Error::from_raw_os_error(errno as i32))
+        }
+    }
+
+    ///Returns origin of notification.
+    pub fn origin(&self) -> ErrorOrigin {
+        self.origin
+    }
+
+    ///Returns type of notification, e.g. ICMP type.
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    ///Returns code of notification, e.g. ICMP code.
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+
+    ///Returns origin specific information, e.g. path MTU.
+    pub fn info(&self) -> u32 {
+        self.info
+    }
+
+    ///Returns origin specific data.
+    pub fn data(&self) -> u32 {
+        self.data
+    }
+
+    ///Returns address of node that reported error, if any.
+    pub fn offender(&self) -> Option<net::SocketAddr> {
+        self.offender
+    }
+
+    ///Returns timestamp that accompanies notification, if any.
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    ///Returns TX timestamp, if notification is one.
+    ///
+    ///Such notifications carry `ENOMSG` error.
+    pub fn tx_timestamp(&self) -> Option<TxTimestamp> {
+        match (self.origin, self.timestamp) {
+            (ErrorOrigin::TxStatus, Some(time)) => Some(TxTimestamp {
+                id: self.data,
+                time
+            }),
+            _ => None
+        }
+    }
+
+    ///Returns updated path MTU, if notification reports that packet was too big.
+    ///
+    ///Such notifications carry `EMSGSIZE` error and are queued either locally,
+    ///when sending packet exceeding known path MTU, or on ICMP error.
+    pub fn path_mtu(&self) -> Option<u32> {
+        match self.origin {
+            ErrorOrigin::Local | ErrorOrigin::Icmp | ErrorOrigin::Icmp6 if self.errno == EMSGSIZE as u32 => Some(self.info),
+            _ => None
+        }
+    }
+
+    ///Returns zerocopy completion, if notification is one.
+    pub fn zerocopy(&self) -> Option<ZerocopyCompletion> {
+        match self.origin {
+            ErrorOrigin::Zerocopy => Some(ZerocopyCompletion {
+                first: self.info,
+                last: self.data,
+                copied: self.code & SO_EE_CODE_ZEROCOPY_COPIED != 0
+            }),
+            _ => None
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Destination address and interface of datagram.
+///
+///Available only on Linux.
+pub struct PacketInfo {
+    ///Local address datagram was sent to.
+    ///
+    ///IPv4-mapped address of dual-stack socket is reported as plain IPv4 one.
+    pub addr: net::IpAddr,
+    ///Index of interface datagram arrived on.
+    pub interface: u32
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Transmit timestamp of sent data.
+///
+///Available only on Linux.
+pub struct TxTimestamp {
+    ///Identifier of send, counted from zero if `Timestamping::OPT_ID` is set.
+    pub id: u32,
+    ///Time when data left kernel.
+    pub time: SystemTime
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Completion of `MSG_ZEROCOPY` sends.
+///
+///Available only on Linux.
+pub struct ZerocopyCompletion {
+    ///Identifier of first completed send.
+    pub first: u32,
+    ///Identifier of last completed send, inclusive.
+    pub last: u32,
+    ///Whether kernel fell back to copying data for the range.
+    pub copied: bool
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl ZerocopyCompletion {
+    ///Returns whether send with specified identifier is completed.
+    pub fn contains(&self, id: u32) -> bool {
+        //Identifiers wrap around.
+        id.wrapping_sub(self.first) <= self.last.wrapping_sub(self.first)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Clone, Debug)]
+///Datagrams received by `recv_from_gro`.
+///
+///Iterates over received bytes split by segment size, last segment may be shorter.
+///Datagram that wasn't coalesced is yielded as single segment, even if it is empty.
+///
+///Available only on Linux.
+pub struct GroSegments<'a> {
+    data: &'a [u8],
+    ///Segments that are not yet iterated.
+    rest: &'a [u8],
+    segment_size: usize,
+    truncated: bool,
+    done: bool
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<'a> GroSegments<'a> {
+    ///Returns all received bytes.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    ///Returns size of segments, which equals to number of received bytes if datagram wasn't coalesced.
+    pub fn segment_size(&self) -> usize {
+        self.segment_size
+    }
+
+    ///Returns whether buffer was too small, so that excess bytes are discarded.
+    ///
+    ///Last segment is then incomplete.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<'a> Iterator for GroSegments<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let (segment, rest) = self.rest.split_at(cmp::min(self.segment_size, self.rest.len()));
+        self.rest = rest;
+        self.done = rest.is_empty();
+        Some(segment)
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+///Converts leading `timespec` of control message, zero means no timestamp.
+fn timespec_to_system_time(data: &[u8]) -> Option<SystemTime> {
+    if data.len() < mem::size_of::<timespec>() {
+        return None;
+    }
+
+    let time: timespec = unsafe { ptr::read_unaligned(data.as_ptr() as *const timespec) };
+
+    match (time.tv_sec, time.tv_nsec) {
+        (0, 0) => None,
+        (sec, nsec) => Some(UNIX_EPOCH + Duration::new(sec as u64, nsec as u32))
+    }
+}
+
+///Returns deadline that is `timeout` away, `None` if it cannot be represented.
+fn deadline_after(timeout: Duration) -> io::Result<Option<Instant>> {
+    if timeout == Duration::from_secs(0) {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "Zero timeout is not allowed."))
+    } else {
+        Ok(Instant::now().checked_add(timeout))
+    }
+}
+
+#[inline]
+fn duration_to_ms_ceil(duration: Duration) -> u64 {
+    let ms = duration.as_secs().saturating_mul(1000).saturating_add(duration.subsec_nanos() as u64 / 1_000_000);
+
+    match duration.subsec_nanos() % 1_000_000 {
+        0 => ms,
+        _ => ms.saturating_add(1)
+    }
+}
+
 #[inline]
-fn ms_to_timeval(timeout_ms: u64) -> timeval {
+fn duration_to_timeval(duration: Duration) -> timeval {
     timeval {
-        tv_sec: timeout_ms as time_t / 1000,
-        tv_usec: (timeout_ms as suseconds_t % 1000) * 1000
+        tv_sec: cmp::min(duration.as_secs(), time_t::MAX as u64) as time_t,
+        tv_usec: duration.subsec_micros() as suseconds_t
+    }
+}
+
+fn timeout_to_timeval(timeout: Option<Duration>) -> io::Result<timeval> {
+    match timeout {
+        Some(timeout) => match duration_to_timeval(timeout) {
+            timeval { tv_sec: 0, tv_usec: 0 } if timeout.subsec_nanos() == 0 => Err(io::Error::new(io::ErrorKind::InvalidInput, "Zero timeout is not allowed.")),
+            //Round up sub-microsecond timeouts as otherwise they would become infinite.
+            timeval { tv_sec: 0, tv_usec: 0 } => Ok(timeval { tv_sec: 0, tv_usec: 1 }),
+            timeout => Ok(timeout)
+        },
+        None => Ok(timeval { tv_sec: 0, tv_usec: 0 })
+    }
+}
+
+fn timeval_to_timeout(timeout: timeval) -> Option<Duration> {
+    match timeout {
+        timeval { tv_sec: 0, tv_usec: 0 } => None,
+        timeout => Some(Duration::new(timeout.tv_sec as u64, timeout.tv_usec as u32 * 1000))
     }
 }
 
+#[inline]
+fn buffer_size_to_raw(size: usize) -> c_int {
+    cmp::min(size, c_int::MAX as usize) as c_int
+}
+
 fn sockets_to_fd_set(sockets: &[&Socket]) -> (c_int, fd_set) {
     let mut max_fd: c_int = 0;
     let mut raw_fds: fd_set = unsafe { mem::zeroed() };
@@ -644,22 +3531,35 @@ fn sockets_to_fd_set(sockets: &[&Socket]) -> (c_int, fd_set) {
 ///Returns number of sockets that are ready.
 ///
 ///If timeout isn't specified then select will be a blocking call.
-pub fn select(read_fds: &[&Socket], write_fds: &[&Socket], except_fds: &[&Socket], timeout_ms: Option<u64>) -> io::Result<c_int> {
-    let (max_read_fd, mut raw_read_fds) = sockets_to_fd_set(read_fds);
-    let (max_write_fd, mut raw_write_fds) = sockets_to_fd_set(write_fds);
-    let (max_except_fd, mut raw_except_fds) = sockets_to_fd_set(except_fds);
+///
+///If interrupted by signal, select is restarted with remaining timeout.
+pub fn select(read_fds: &[&Socket], write_fds: &[&Socket], except_fds: &[&Socket], timeout_ms: Option<u64>) -> Result<c_int, SocketError> {
+    select_raw(read_fds, write_fds, except_fds, timeout_ms).operation(Operation::Select)
+}
 
-    let nfds = cmp::max(max_read_fd, cmp::max(max_write_fd, max_except_fd)) + 1;
+fn select_raw(read_fds: &[&Socket], write_fds: &[&Socket], except_fds: &[&Socket], timeout_ms: Option<u64>) -> io::Result<c_int> {
+    //Timeout too large to be represented as deadline is treated as infinite.
+    let deadline = timeout_ms.and_then(|timeout_ms| Instant::now().checked_add(Duration::from_millis(timeout_ms)));
 
-    unsafe {
-        match libc::select(nfds,
-                           if max_read_fd > 0 { &mut raw_read_fds } else { ptr::null_mut() },
-                           if max_write_fd > 0 { &mut raw_write_fds } else { ptr::null_mut() },
-                           if max_except_fd > 0 { &mut raw_except_fds } else { ptr::null_mut() },
-                           if let Some(timeout_ms) = timeout_ms { &mut ms_to_timeval(timeout_ms) } else { ptr::null_mut() } ) {
-            SOCKET_ERROR => Err(io::Error::last_os_error()),
-            result @ _ => Ok(result)
+    retry_interrupted(|| {
+        //Sets are modified by select so they have to be re-created on restart.
+        let (max_read_fd, mut raw_read_fds) = sockets_to_fd_set(read_fds);
+        let (max_write_fd, mut raw_write_fds) = sockets_to_fd_set(write_fds);
+        let (max_except_fd, mut raw_except_fds) = sockets_to_fd_set(except_fds);
+
+        let nfds = cmp::max(max_read_fd, cmp::max(max_write_fd, max_except_fd)) + 1;
+        let mut timeout = deadline.map(|deadline| duration_to_timeval(deadline.saturating_duration_since(Instant::now())));
+
+        unsafe {
+            match libc::select(nfds,
+                               if max_read_fd > 0 { &mut raw_read_fds } else { ptr::null_mut() },
+                               if max_write_fd > 0 { &mut raw_write_fds } else { ptr::null_mut() },
+                               if max_except_fd > 0 { &mut raw_except_fds } else { ptr::null_mut() },
+                               if let Some(ref mut timeout) = timeout { timeout } else { ptr::null_mut() } ) {
+                SOCKET_ERROR => Err(io::Error::last_os_error()),
+                result => Ok(result)
 
+            }
         }
-    }
+    })
 }
@@ -3,10 +3,14 @@ use std::os::raw::*;
 use std::net;
 use std::mem;
 use std::ptr;
+use std::cmp;
+use std::slice;
+use std::mem::MaybeUninit;
 use std::sync::{Once, ONCE_INIT};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::time::{Duration, Instant};
 
-#[cfg(feature = "safe_buffer_len")]
-use std::cmp;
+use super::{ErrorClass, Operation, ResultExt, SocketError, TransferError};
 
 mod winapi {
     #![allow(bad_style)]
@@ -25,9 +29,45 @@ mod winapi {
 	pub use self::winapi::shared::ntdef::{
 		CHAR,
 		HANDLE,
+		ULONG,
+		WCHAR
     };
 
     pub use self::winapi::shared::winerror::{
+        WSAEINTR,
+        WSAENOBUFS,
+        WSAETIMEDOUT,
+        WSAECONNRESET,
+        WSAECONNABORTED,
+        WSAECONNREFUSED,
+        WSAENOTCONN,
+        WSAENETRESET,
+        WSAEDISCON,
+        WSAEADDRINUSE,
+        WSAEADDRNOTAVAIL,
+        WSAEAFNOSUPPORT,
+        WSAENETUNREACH,
+        WSAEHOSTUNREACH,
+        WSAENETDOWN,
+        WSAEHOSTDOWN,
+        WSAEDESTADDRREQ,
+        WSAEACCES,
+        WSAEBADF,
+        WSAENOTSOCK,
+        WSAEFAULT,
+        WSAEOPNOTSUPP,
+        WSAENOPROTOOPT,
+        WSAEPROTONOSUPPORT,
+        WSAEPROTOTYPE,
+        ERROR_SUCCESS,
+        ERROR_BUFFER_OVERFLOW,
+        ERROR_NO_DATA,
+        WSAESOCKTNOSUPPORT,
+        WSAEMSGSIZE,
+        WSAEWOULDBLOCK,
+        WSAEINPROGRESS,
+        WSAEALREADY,
+        WSAEISCONN,
         WSAESHUTDOWN,
         WSAEINVAL
     };
@@ -56,6 +96,43 @@ mod winapi {
         SOCKADDR_STORAGE_LH,
         SOCKADDR_IN,
         SOCKADDR,
+
+        SOL_SOCKET,
+        SO_RCVTIMEO,
+        SO_SNDTIMEO,
+        SO_RCVBUF,
+        SO_SNDBUF,
+        SO_LINGER,
+        SO_REUSEADDR,
+        SO_BROADCAST,
+        SO_ERROR,
+
+        IPPROTO_IP,
+        IPPROTO_IPV6,
+    };
+
+    pub use self::winapi::shared::ws2ipdef::{
+        IPV6_V6ONLY,
+        IPV6_UNICAST_HOPS,
+        IPV6_TCLASS,
+        IP_TTL,
+        IP_TOS,
+
+        IP_MREQ,
+        IP_MREQ_SOURCE,
+        IPV6_MREQ,
+        IP_ADD_MEMBERSHIP,
+        IP_DROP_MEMBERSHIP,
+        IP_ADD_SOURCE_MEMBERSHIP,
+        IP_DROP_SOURCE_MEMBERSHIP,
+        IP_MULTICAST_IF,
+        IP_MULTICAST_TTL,
+        IP_MULTICAST_LOOP,
+        IPV6_JOIN_GROUP,
+        IPV6_LEAVE_GROUP,
+        IPV6_MULTICAST_IF,
+        IPV6_MULTICAST_HOPS,
+        IPV6_MULTICAST_LOOP
     };
 
     pub const SOCK_NONBLOCK: winapi::ctypes::c_int = 0o0004000;
@@ -77,6 +154,12 @@ mod winapi {
         INVALID_SOCKET,
         SOCKET_ERROR,
         FIONBIO,
+        FIONREAD,
+
+        MSG_PEEK,
+        MSG_WAITALL,
+        MSG_OOB,
+        MSG_DONTROUTE,
 
         FD_SETSIZE,
         WSADESCRIPTION_LEN,
@@ -85,12 +168,14 @@ mod winapi {
         WSADATA,
         fd_set,
         timeval,
+        linger,
         LPWSADATA,
 
         WSAStartup,
         WSACleanup,
 
         getsockname,
+        getpeername,
         socket,
         bind,
         listen,
@@ -108,8 +193,32 @@ mod winapi {
         select
     };
 
+    pub use self::winapi::um::iphlpapi::GetAdaptersAddresses;
+
+    pub use self::winapi::um::iptypes::{
+        IP_ADAPTER_ADDRESSES_LH,
+        GAA_FLAG_SKIP_ANYCAST,
+        GAA_FLAG_SKIP_MULTICAST,
+        GAA_FLAG_SKIP_DNS_SERVER
+    };
+
+    pub use self::winapi::shared::ipifcons::{
+        IF_TYPE_PPP,
+        IF_TYPE_SOFTWARE_LOOPBACK,
+        IF_TYPE_TUNNEL
+    };
+
     // Currently not available in `winapi`.
     pub const HANDLE_FLAG_INHERIT: DWORD = 1;
+    pub const MCAST_JOIN_SOURCE_GROUP: winapi::ctypes::c_int = 45;
+    pub const MCAST_LEAVE_SOURCE_GROUP: winapi::ctypes::c_int = 46;
+
+    #[repr(C)]
+    pub struct GROUP_SOURCE_REQ {
+        pub gsr_interface: winapi::ctypes::c_ulong,
+        pub gsr_group: SOCKADDR_STORAGE_LH,
+        pub gsr_source: SOCKADDR_STORAGE_LH
+    }
 
     pub use self::winapi::um::handleapi::{
     	SetHandleInformation,
@@ -120,9 +229,9 @@ mod winapi {
 macro_rules! impl_into_trait {
     ($($t:ty), +) => {
         $(
-            impl Into<c_int> for $t {
-                fn into(self) -> c_int {
-                    self as c_int
+            impl From<$t> for c_int {
+                fn from(value: $t) -> c_int {
+                    value as c_int
                 }
             }
         )+
@@ -166,15 +275,43 @@ pub mod Protocol {
     pub const ICMPv6: c_int = winapi::IPPROTO_ICMPV6 as i32;
 }
 
-#[allow(non_snake_case)]
-///Possible flags for `accept4()`
-///
-///Note that these flags correspond to emulated constants that are not represented
-///in the OS in this way.
-bitflags! (pub flags AcceptFlags: c_int {
-    const NON_BLOCKING    = winapi::SOCK_NONBLOCK,
-    const NON_INHERITABLE = winapi::SOCK_CLOEXEC,
-});
+bitflags! {
+    ///Possible flags for `accept4()`
+    ///
+    ///Note that these flags correspond to emulated constants that are not represented
+    ///in the OS in this way.
+    pub struct AcceptFlags: c_int {
+        const NON_BLOCKING    = winapi::SOCK_NONBLOCK;
+        const NON_INHERITABLE = winapi::SOCK_CLOEXEC;
+    }
+}
+
+///Alias to `AcceptFlags::NON_BLOCKING`
+pub const NON_BLOCKING: AcceptFlags = AcceptFlags::NON_BLOCKING;
+///Alias to `AcceptFlags::NON_INHERITABLE`
+pub const NON_INHERITABLE: AcceptFlags = AcceptFlags::NON_INHERITABLE;
+
+bitflags! {
+    ///Flags for send and receive operations.
+    ///
+    ///Can be used in place of raw `c_int` flags.
+    pub struct MsgFlags: c_int {
+        ///Peeks at incoming data without removing it from queue.
+        const PEEK      = winapi::MSG_PEEK;
+        ///Waits until whole buffer is filled.
+        const WAITALL   = winapi::MSG_WAITALL;
+        ///Sends or receives out-of-band data.
+        const OOB       = winapi::MSG_OOB;
+        ///Bypasses routing and sends only to directly connected hosts.
+        const DONTROUTE = winapi::MSG_DONTROUTE;
+    }
+}
+
+impl From<MsgFlags> for c_int {
+    fn from(flags: MsgFlags) -> c_int {
+        flags.bits()
+    }
+}
 
 #[repr(i32)]
 #[derive(Copy, Clone)]
@@ -192,14 +329,20 @@ impl_into_trait!(ShutdownType);
 
 ///Raw socket
 pub struct Socket {
-    inner: winapi::SOCKET
+    inner: winapi::SOCKET,
+    ///Whether zero linger timeout is set, so that `Drop` skips orderly shutdown.
+    abortive_close: AtomicBool,
+    ///Whether socket is in blocking mode, as Windows doesn't allow to query it.
+    blocking: AtomicBool,
+    ///Address family of socket, `AF_UNSPEC` until queried.
+    family: AtomicI32
 }
 
 impl Socket {
     ///Initializes new socket.
     ///
     ///Corresponds to C connect()
-    pub fn new(family: c_int, _type: c_int, protocol: c_int) -> io::Result<Socket> {
+    pub fn new(family: c_int, _type: c_int, protocol: c_int) -> Result<Socket, SocketError> {
         static INIT: Once = ONCE_INIT;
 
         INIT.call_once(|| {
@@ -209,10 +352,8 @@ impl Socket {
 
         unsafe {
             match winapi::socket(family, _type, protocol) {
-                winapi::INVALID_SOCKET => Err(io::Error::last_os_error()),
-                fd => Ok(Socket {
-                    inner: fd
-                }),
+                winapi::INVALID_SOCKET => Err(SocketError::last_os_error(Operation::Create)),
+                fd => Ok(Socket::from_raw_socket(fd as winapi::STD_SOCKET)),
             }
         }
     }
@@ -229,36 +370,57 @@ impl Socket {
     ///Wraps `getsockname()`
     ///
     ///Available for binded/connected sockets.
-    pub fn name(&self) -> io::Result<net::SocketAddr> {
+    pub fn name(&self) -> Result<net::SocketAddr, SocketError> {
         unsafe {
             let mut storage: winapi::SOCKADDR_STORAGE_LH = mem::zeroed();
             let mut len = mem::size_of_val(&storage) as c_int;
 
             match winapi::getsockname(self.inner, &mut storage as *mut _ as *mut _, &mut len) {
-                winapi::SOCKET_ERROR => Err(io::Error::last_os_error()),
-                _ => sockaddr_to_addr(&storage, len)
+                winapi::SOCKET_ERROR => Err(SocketError::last_os_error(Operation::Name)),
+                _ => sockaddr_to_addr(&storage, len).operation(Operation::Name)
             }
         }
     }
 
+    ///Returns address family of socket.
+    ///
+    ///It is queried once with `getsockname()` and cached.
+    fn family(&self) -> c_int {
+        match self.family.load(Ordering::Relaxed) {
+            winapi::AF_UNSPEC => unsafe {
+                let mut storage: winapi::SOCKADDR_STORAGE_LH = mem::zeroed();
+                let mut len = mem::size_of_val(&storage) as c_int;
+
+                match winapi::getsockname(self.inner, &mut storage as *mut _ as *mut _, &mut len) {
+                    winapi::SOCKET_ERROR => winapi::AF_UNSPEC,
+                    _ => {
+                        self.family.store(storage.ss_family as c_int, Ordering::Relaxed);
+                        storage.ss_family as c_int
+                    }
+                }
+            },
+            family => family
+        }
+    }
+
     ///Binds socket to address.
-    pub fn bind(&self, addr: &net::SocketAddr) -> io::Result<()> {
-        let (addr, len) = get_raw_addr(addr);
+    pub fn bind(&self, addr: &net::SocketAddr) -> Result<(), SocketError> {
+        let (addr, len) = get_raw_addr(addr, self.family());
 
         unsafe {
-            match winapi::bind(self.inner, addr, len) {
+            match winapi::bind(self.inner, &addr as *const _ as *const _, len) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Bind))
             }
         }
     }
 
     ///Listens for incoming connections on this socket.
-    pub fn listen(&self, backlog: c_int) -> io::Result<()> {
+    pub fn listen(&self, backlog: c_int) -> Result<(), SocketError> {
         unsafe {
             match winapi::listen(self.inner, backlog) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Listen))
             }
         }
     }
@@ -266,7 +428,16 @@ impl Socket {
     ///Receives some bytes from socket
     ///
     ///Number of received bytes is returned on success
-    pub fn recv(&self, buf: &mut [u8], flags: c_int) -> io::Result<usize> {
+    pub fn recv<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<usize, SocketError> {
+        self.recv_uninit(as_uninit_mut(buf), flags)
+    }
+
+    ///Receives some bytes from socket into uninitialised buffer.
+    ///
+    ///Number of received bytes is returned on success.
+    ///Only that many leading bytes of `buf` are initialised.
+    pub fn recv_uninit<F: Into<c_int>>(&self, buf: &mut [MaybeUninit<u8>], flags: F) -> Result<usize, SocketError> {
+        let flags = flags.into();
         #[cfg(feature = "safe_buffer_len")]
         let len = cmp::min(buf.len(), i32::max_value() as usize) as i32;
         #[cfg(not(feature = "safe_buffer_len"))]
@@ -282,7 +453,7 @@ impl Socket {
                         Ok(0)
                     }
                     else {
-                        Err(error)
+                        Err(SocketError::new(Operation::Recv, error))
                     }
                 },
                 n => Ok(n as usize)
@@ -293,7 +464,16 @@ impl Socket {
     ///Receives some bytes from socket
     ///
     ///Number of received bytes and remote address are returned on success.
-    pub fn recv_from(&self, buf: &mut [u8], flags: c_int) -> io::Result<(usize, net::SocketAddr)> {
+    pub fn recv_from<F: Into<c_int>>(&self, buf: &mut [u8], flags: F) -> Result<(usize, net::SocketAddr), SocketError> {
+        self.recv_from_uninit(as_uninit_mut(buf), flags)
+    }
+
+    ///Receives some bytes from socket into uninitialised buffer.
+    ///
+    ///Number of received bytes and remote address are returned on success.
+    ///Only that many leading bytes of `buf` are initialised.
+    pub fn recv_from_uninit<F: Into<c_int>>(&self, buf: &mut [MaybeUninit<u8>], flags: F) -> Result<(usize, net::SocketAddr), SocketError> {
+        let flags = flags.into();
         #[cfg(feature = "safe_buffer_len")]
         let len = cmp::min(buf.len(), i32::max_value() as usize) as i32;
         #[cfg(not(feature = "safe_buffer_len"))]
@@ -309,25 +489,34 @@ impl Socket {
                     let raw_code = error.raw_os_error().unwrap();
 
                     if raw_code == winapi::WSAESHUTDOWN as i32 {
-                        let peer_addr = sockaddr_to_addr(&storage, storage_len)?;
+                        let peer_addr = sockaddr_to_peer_addr(&storage, storage_len).operation(Operation::Recv)?;
                         Ok((0, peer_addr))
                     }
                     else {
-                        Err(error)
+                        Err(SocketError::new(Operation::Recv, error))
                     }
                 },
                 n => {
-                    let peer_addr = sockaddr_to_addr(&storage, storage_len)?;
+                    let peer_addr = sockaddr_to_peer_addr(&storage, storage_len).operation(Operation::Recv)?;
                     Ok((n as usize, peer_addr))
                 }
             }
         }
     }
 
+    ///Receives some bytes from socket without removing them from receive queue.
+    ///
+    ///Number of peeked bytes and remote address are returned on success.
+    ///Subsequent receive returns the same data.
+    pub fn peek_from(&self, buf: &mut [u8]) -> Result<(usize, net::SocketAddr), SocketError> {
+        self.recv_from(buf, winapi::MSG_PEEK)
+    }
+
     ///Sends some bytes through socket.
     ///
     ///Number of sent bytes is returned.
-    pub fn send(&self, buf: &[u8], flags: c_int) -> io::Result<usize> {
+    pub fn send<F: Into<c_int>>(&self, buf: &[u8], flags: F) -> Result<usize, SocketError> {
+        let flags = flags.into();
         #[cfg(feature = "safe_buffer_len")]
         let len = cmp::min(buf.len(), i32::max_value() as usize) as i32;
         #[cfg(not(feature = "safe_buffer_len"))]
@@ -343,7 +532,7 @@ impl Socket {
                         Ok(0)
                     }
                     else {
-                        Err(error)
+                        Err(SocketError::new(Operation::Send, error))
                     }
                 },
                 n => Ok(n as usize)
@@ -357,15 +546,16 @@ impl Socket {
     ///
     ///Note: the socket will be bound, if it isn't already.
     ///Use method `name` to determine address.
-    pub fn send_to(&self, buf: &[u8], peer_addr: &net::SocketAddr, flags: c_int) -> io::Result<usize> {
+    pub fn send_to<F: Into<c_int>>(&self, buf: &[u8], peer_addr: &net::SocketAddr, flags: F) -> Result<usize, SocketError> {
+        let flags = flags.into();
         #[cfg(feature = "safe_buffer_len")]
         let len = cmp::min(buf.len(), i32::max_value() as usize) as i32;
         #[cfg(not(feature = "safe_buffer_len"))]
         let len = buf.len() as i32;
-        let (addr, addr_len) = get_raw_addr(peer_addr);
+        let (addr, addr_len) = get_raw_addr(peer_addr, self.family());
 
         unsafe {
-            match winapi::sendto(self.inner, buf.as_ptr() as *const c_char, len, flags, addr, addr_len) {
+            match winapi::sendto(self.inner, buf.as_ptr() as *const c_char, len, flags, &addr as *const _ as *const _, addr_len) {
                 -1 => {
                     let error = io::Error::last_os_error();
                     let raw_code = error.raw_os_error().unwrap();
@@ -374,7 +564,7 @@ impl Socket {
                         Ok(0)
                     }
                     else {
-                        Err(error)
+                        Err(SocketError::new(Operation::Send, error))
                     }
                 },
                 n => Ok(n as usize)
@@ -390,7 +580,7 @@ impl Socket {
     ///
     /// * `AcceptFlags::NON_BLOCKING`    – Mark the newly created socket as non-blocking
     /// * `AcceptFlags::NON_INHERITABLE` – Mark the newly created socket as not inheritable by client processes
-    pub fn accept4(&self, flags: AcceptFlags) -> io::Result<(Socket, net::SocketAddr)> {
+    pub fn accept4(&self, flags: AcceptFlags) -> Result<(Socket, net::SocketAddr), SocketError> {
         self.accept().map(|(sock, addr)| {
             // Emulate the two most common (and useful) `accept4` flags
             sock.set_blocking(!flags.contains(NON_BLOCKING)).expect("Setting newly obtained client socket blocking mode");
@@ -401,35 +591,251 @@ impl Socket {
     }
 
     ///Accepts incoming connection.
-    pub fn accept(&self) -> io::Result<(Socket, net::SocketAddr)> {
+    pub fn accept(&self) -> Result<(Socket, net::SocketAddr), SocketError> {
         unsafe {
             let mut storage: winapi::SOCKADDR_STORAGE_LH = mem::zeroed();
             let mut len = mem::size_of_val(&storage) as c_int;
 
             match winapi::accept(self.inner, &mut storage as *mut _ as *mut _, &mut len) {
-                winapi::INVALID_SOCKET => Err(io::Error::last_os_error()),
+                winapi::INVALID_SOCKET => Err(SocketError::last_os_error(Operation::Accept)),
                 sock @ _ => {
-                    let addr = sockaddr_to_addr(&storage, len)?;
-                    Ok((Socket { inner: sock }, addr))
+                    let addr = sockaddr_to_peer_addr(&storage, len).operation(Operation::Accept)?;
+                    let sock = Socket::from_raw_socket(sock as winapi::STD_SOCKET);
+                    //Accepted socket inherits blocking mode of listening one.
+                    sock.blocking.store(self.blocking.load(Ordering::Relaxed), Ordering::Relaxed);
+                    Ok((sock, addr))
                 }
             }
         }
     }
 
     ///Connects socket with remote address.
-    pub fn connect(&self, addr: &net::SocketAddr) -> io::Result<()> {
-        let (addr, len) = get_raw_addr(addr);
+    pub fn connect(&self, addr: &net::SocketAddr) -> Result<(), SocketError> {
+        let (addr, len) = get_raw_addr(addr, self.family());
 
         unsafe {
-            match winapi::connect(self.inner, addr, len) {
+            match winapi::connect(self.inner, &addr as *const _ as *const _, len) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Connect))
+            }
+        }
+    }
+
+    ///Starts connecting socket with remote address in non-blocking mode.
+    ///
+    ///Socket is switched into non-blocking mode and stays in it.
+    ///
+    ///`WSAEWOULDBLOCK`, `WSAEINPROGRESS` and `WSAEALREADY` are reported as `InProgress` as connection
+    ///continues asynchronously, while `WSAEISCONN` is reported as `Connected`.
+    pub fn connect_nonblocking(&self, addr: &net::SocketAddr) -> Result<ConnectState<'_>, SocketError> {
+        self.set_blocking(false).operation(Operation::Ioctl)?;
+
+        match self.connect(addr) {
+            Ok(()) => Ok(ConnectState::Connected),
+            Err(error) => match error.raw_os_error() {
+                Some(code) if is_connect_in_progress(code) => Ok(ConnectState::InProgress(PendingConnect { socket: self })),
+                Some(code) if code == winapi::WSAEISCONN as i32 => Ok(ConnectState::Connected),
+                _ => Err(error)
             }
         }
     }
 
+    ///Connects socket with remote address, failing with `TimedOut` if it takes longer than `timeout`.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `FIONBIO` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    ///
+    ///Note: on timeout connection attempt is not cancelled, so socket should be discarded.
+    pub fn connect_timeout(&self, addr: &net::SocketAddr, timeout: Duration) -> Result<(), SocketError> {
+        let deadline = deadline_after(timeout).operation(Operation::Connect)?;
+
+        self.with_nonblocking(|| match self.connect_nonblocking(addr).map_err(SocketError::into_inner)? {
+            ConnectState::Connected => Ok(()),
+            ConnectState::InProgress(pending) => loop {
+                self.wait_ready(false, deadline)?;
+
+                if pending.poll_complete_raw()? {
+                    return Ok(());
+                }
+            }
+        }).operation(Operation::Connect)
+    }
+
+    ///Receives some bytes from socket, failing with `TimedOut` if nothing arrives within `timeout`.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `FIONBIO` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    pub fn recv_timeout<F: Into<c_int>>(&self, buf: &mut [u8], flags: F, timeout: Duration) -> Result<usize, SocketError> {
+        let flags = flags.into();
+        let deadline = deadline_after(timeout).operation(Operation::Recv)?;
+
+        self.with_nonblocking(|| loop {
+            match self.recv(buf, flags).map_err(SocketError::into_inner) {
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => self.wait_ready(true, deadline)?,
+                result => return result
+            }
+        }).operation(Operation::Recv)
+    }
+
+    ///Sends some bytes through socket, failing with `TimedOut` if it cannot proceed within `timeout`.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `FIONBIO` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    pub fn send_timeout<F: Into<c_int>>(&self, buf: &[u8], flags: F, timeout: Duration) -> Result<usize, SocketError> {
+        let flags = flags.into();
+        let deadline = deadline_after(timeout).operation(Operation::Send)?;
+
+        self.with_nonblocking(|| loop {
+            match self.send(buf, flags).map_err(SocketError::into_inner) {
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => self.wait_ready(false, deadline)?,
+                result => return result
+            }
+        }).operation(Operation::Send)
+    }
+
+    ///Accepts incoming connection, failing with `TimedOut` if none arrives within `timeout`.
+    ///
+    ///Accepted socket is in blocking mode.
+    ///
+    ///Socket's original blocking mode is restored afterwards.
+    ///Note: mode is toggled via `FIONBIO` on socket itself, so other threads using it
+    ///observe non-blocking mode meanwhile.
+    pub fn accept_timeout(&self, timeout: Duration) -> Result<(Socket, net::SocketAddr), SocketError> {
+        let deadline = deadline_after(timeout).operation(Operation::Accept)?;
+
+        self.with_nonblocking(|| loop {
+            match self.accept4(AcceptFlags::empty()).map_err(SocketError::into_inner) {
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => self.wait_ready(true, deadline)?,
+                result => return result
+            }
+        }).operation(Operation::Accept)
+    }
+
+    ///Sends whole buffer through stream socket.
+    ///
+    ///Partial sends are continued until every byte is sent.
+    ///
+    ///If socket is in non-blocking mode, it waits for socket to become writable.
+    ///Whole call is limited by `timeout`, if it is specified, after which `TimedOut` is returned,
+    ///so that peer can't prolong it by accepting few bytes at a time.
+    ///Zero `timeout` disables waiting so that `WouldBlock` is returned instead.
+    ///
+    ///On error number of bytes that were sent is reported.
+    pub fn send_all<F: Into<c_int>>(&self, buf: &[u8], flags: F, timeout: Option<Duration>) -> Result<(), TransferError> {
+        let flags = flags.into();
+        let mut sent = 0;
+        //Unrepresentable deadline is as good as none.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+
+        while sent < buf.len() {
+            let result = match self.send(&buf[sent..], flags).map_err(SocketError::into_inner) {
+                Ok(0) => Err(io::Error::new(io::ErrorKind::WriteZero, "Failed to send whole buffer.")),
+                Ok(n) => {
+                    sent += n;
+                    Ok(())
+                },
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock && timeout != Some(Duration::from_secs(0)) => self.wait_ready(false, deadline),
+                Err(error) => Err(error)
+            };
+
+            if let Err(error) = result {
+                return Err(TransferError::new(sent, error));
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Receives exactly enough bytes from stream socket to fill buffer.
+    ///
+    ///Fails with `UnexpectedEof` if connection is closed before buffer is filled.
+    ///
+    ///If socket is in non-blocking mode, it waits for socket to become readable.
+    ///Whole call is limited by `timeout`, if it is specified, after which `TimedOut` is returned,
+    ///so that peer can't prolong it by sending few bytes at a time.
+    ///Zero `timeout` disables waiting so that `WouldBlock` is returned instead.
+    ///
+    ///On error number of bytes that were received into leading part of `buf` is reported.
+    ///`MSG_PEEK` must not be used as it would never make progress.
+    pub fn recv_exact<F: Into<c_int>>(&self, buf: &mut [u8], flags: F, timeout: Option<Duration>) -> Result<(), TransferError> {
+        let flags = flags.into();
+        let mut received = 0;
+        //Unrepresentable deadline is as good as none.
+        let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+
+        while received < buf.len() {
+            let result = match self.recv(&mut buf[received..], flags).map_err(SocketError::into_inner) {
+                Ok(0) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Failed to fill whole buffer.")),
+                Ok(n) => {
+                    received += n;
+                    Ok(())
+                },
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock && timeout != Some(Duration::from_secs(0)) => self.wait_ready(true, deadline),
+                Err(error) => Err(error)
+            };
+
+            if let Err(error) = result {
+                return Err(TransferError::new(received, error));
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Runs operation with socket in non-blocking mode, restoring original mode afterwards.
+    ///
+    ///Failure to restore mode is reported only if operation succeeded, whose result is dropped then.
+    fn with_nonblocking<T, F: FnOnce() -> io::Result<T>>(&self, op: F) -> io::Result<T> {
+        let is_blocking = self.is_blocking()?;
+
+        if is_blocking {
+            self.set_blocking(false)?;
+        }
+
+        let result = op();
+        let restored = if is_blocking { self.set_blocking(true) } else { Ok(()) };
+
+        //Failure of operation takes precedence over failure to restore mode.
+        result.and_then(|value| restored.map(|_| value))
+    }
+
+    ///Waits until socket is ready for reading or writing, failing with `TimedOut` after deadline.
+    ///
+    ///Without deadline waits indefinitely.
+    fn wait_ready(&self, read: bool, deadline: Option<Instant>) -> io::Result<()> {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, "Operation timed out."));
+                }
+
+                Some(duration_to_ms_ceil(deadline - now))
+            },
+            None => None
+        };
+
+        let result = if read {
+            select_raw(&[self], &[], &[self], timeout)?
+        } else {
+            select_raw(&[], &[self], &[self], timeout)?
+        };
+
+        match result {
+            0 => Err(io::Error::new(io::ErrorKind::TimedOut, "Operation timed out.")),
+            _ => Ok(())
+        }
+    }
+
     ///Retrieves socket option.
-    pub fn get_opt<T>(&self, level: c_int, name: c_int) -> io::Result<T> {
+    pub fn get_opt<T>(&self, level: c_int, name: c_int) -> Result<T, SocketError> {
+        self.get_opt_raw(level, name).operation(Operation::GetOpt)
+    }
+
+    fn get_opt_raw<T>(&self, level: c_int, name: c_int) -> io::Result<T> {
         unsafe {
             let mut value: T = mem::zeroed();
             let value_ptr = &mut value as *mut T as *mut c_char;
@@ -445,7 +851,11 @@ impl Socket {
     ///Sets socket option
     ///
     ///Value is generally integer or C struct.
-    pub fn set_opt<T>(&self, level: c_int, name: c_int, value: T) -> io::Result<()> {
+    pub fn set_opt<T>(&self, level: c_int, name: c_int, value: T) -> Result<(), SocketError> {
+        self.set_opt_raw(level, name, value).operation(Operation::SetOpt)
+    }
+
+    fn set_opt_raw<T>(&self, level: c_int, name: c_int, value: T) -> io::Result<()> {
         unsafe {
             let value = &value as *const T as *const c_char;
 
@@ -459,21 +869,63 @@ impl Socket {
     ///Sets I/O parameters of socket.
     ///
     ///It uses `ioctlsocket` under hood.
-    pub fn ioctl(&self, request: c_int, value: c_ulong) -> io::Result<()> {
+    pub fn ioctl(&self, request: c_int, value: c_ulong) -> Result<(), SocketError> {
+        self.ioctl_raw(request, value).operation(Operation::Ioctl)
+    }
+
+    fn ioctl_raw(&self, request: c_int, value: c_ulong) -> io::Result<()> {
+        let mut value = value;
+
         unsafe {
-            let mut value = value;
-            let value = &mut value as *mut c_ulong;
+            self.ioctl_mut_raw(request, &mut value)
+        }
+    }
 
-            match winapi::ioctlsocket(self.inner, request, value) {
-                0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
-            }
+    ///Controls I/O parameters of socket, passing pointer to `value`.
+    ///
+    ///Kernel may both read and write `value`, so it is used to retrieve output of request.
+    ///
+    ///# Safety
+    ///
+    ///`T` must be the type that `request` expects, as kernel accesses it without bounds checks.
+    pub unsafe fn ioctl_mut<T>(&self, request: c_int, value: &mut T) -> Result<(), SocketError> {
+        self.ioctl_mut_raw(request, value).operation(Operation::Ioctl)
+    }
+
+    unsafe fn ioctl_mut_raw<T>(&self, request: c_int, value: &mut T) -> io::Result<()> {
+        match winapi::ioctlsocket(self.inner, request, value as *mut T as *mut c_ulong) {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error())
         }
     }
 
+    ///Returns number of bytes that can be received without blocking.
+    ///
+    ///Wraps `FIONREAD`.
+    ///For datagram sockets it is size of next datagram.
+    pub fn bytes_available(&self) -> io::Result<usize> {
+        let mut value: c_ulong = 0;
+
+        unsafe {
+            self.ioctl_mut_raw(winapi::FIONREAD as c_int, &mut value)?;
+        }
+
+        Ok(value as usize)
+    }
+
     ///Sets non-blocking mode.
     pub fn set_blocking(&self, value: bool) -> io::Result<()> {
-        self.ioctl(winapi::FIONBIO as c_int, (!value) as c_ulong)
+        self.ioctl_raw(winapi::FIONBIO as c_int, (!value) as c_ulong)?;
+        self.blocking.store(value, Ordering::Relaxed);
+        Ok(())
+    }
+
+    ///Returns whether socket is in blocking mode.
+    ///
+    ///Windows doesn't allow to query it, so mode set by `set_blocking` is tracked instead.
+    ///Sockets adopted by `from_raw_socket` are assumed to be blocking.
+    fn is_blocking(&self) -> io::Result<bool> {
+        Ok(self.blocking.load(Ordering::Relaxed))
     }
 
     ///Sets whether this socket will be inherited by child processes or not.
@@ -500,12 +952,417 @@ impl Socket {
         }
 	}
 
+    ///Retrieves and clears pending socket error.
+    ///
+    ///Wraps `SO_ERROR`.
+    ///
+    ///For example, outcome of non-blocking `connect` is reported this way once socket becomes writable.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        match self.get_opt_raw::<c_int>(winapi::SOL_SOCKET, winapi::SO_ERROR)? {
+            0 => Ok(None),
+            error => Ok(Some(io::Error::from_raw_os_error(error)))
+        }
+    }
+
+    ///Sets timeout for receive operations.
+    ///
+    ///Wraps `SO_RCVTIMEO`. `None` means that operation blocks indefinitely.
+    ///
+    ///Zero duration is rejected with `InvalidInput` as system would treat it the same as `None`.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_opt_raw(winapi::SOL_SOCKET, winapi::SO_RCVTIMEO, timeout_to_ms(timeout)?)
+    }
+
+    ///Retrieves timeout for receive operations.
+    ///
+    ///Wraps `SO_RCVTIMEO`.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.get_opt_raw::<winapi::DWORD>(winapi::SOL_SOCKET, winapi::SO_RCVTIMEO).map(ms_to_timeout)
+    }
+
+    ///Sets timeout for send operations.
+    ///
+    ///Wraps `SO_SNDTIMEO`. `None` means that operation blocks indefinitely.
+    ///
+    ///Zero duration is rejected with `InvalidInput` as system would treat it the same as `None`.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_opt_raw(winapi::SOL_SOCKET, winapi::SO_SNDTIMEO, timeout_to_ms(timeout)?)
+    }
+
+    ///Retrieves timeout for send operations.
+    ///
+    ///Wraps `SO_SNDTIMEO`.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.get_opt_raw::<winapi::DWORD>(winapi::SOL_SOCKET, winapi::SO_SNDTIMEO).map(ms_to_timeout)
+    }
+
+    ///Sets size of receive buffer in bytes.
+    ///
+    ///Wraps `SO_RCVBUF`.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(winapi::SOL_SOCKET, winapi::SO_RCVBUF, cmp::min(size, c_int::max_value() as usize) as c_int)
+    }
+
+    ///Retrieves size of receive buffer in bytes.
+    ///
+    ///Wraps `SO_RCVBUF`.
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        self.get_opt_raw::<c_int>(winapi::SOL_SOCKET, winapi::SO_RCVBUF).map(|size| size as usize)
+    }
+
+    ///Sets size of send buffer in bytes.
+    ///
+    ///Wraps `SO_SNDBUF`.
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.set_opt_raw(winapi::SOL_SOCKET, winapi::SO_SNDBUF, cmp::min(size, c_int::max_value() as usize) as c_int)
+    }
+
+    ///Retrieves size of send buffer in bytes.
+    ///
+    ///Wraps `SO_SNDBUF`.
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        self.get_opt_raw::<c_int>(winapi::SOL_SOCKET, winapi::SO_SNDBUF).map(|size| size as usize)
+    }
+
+    ///Sets linger behaviour on close.
+    ///
+    ///Wraps `SO_LINGER`.
+    ///
+    ///When `Some`, close blocks until pending data is sent or timeout expires.
+    ///Timeout is measured in whole seconds and is rounded up.
+    ///
+    ///Zero timeout results in abortive close i.e. connection is reset instead of orderly shutdown.
+    pub fn set_linger(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let value = match timeout {
+            Some(timeout) => winapi::linger {
+                l_onoff: 1,
+                l_linger: cmp::min(timeout.as_secs() + (timeout.subsec_nanos() > 0) as u64, winapi::USHORT::max_value() as u64) as winapi::USHORT
+            },
+            None => winapi::linger {
+                l_onoff: 0,
+                l_linger: 0
+            }
+        };
+
+        self.set_opt_raw(winapi::SOL_SOCKET, winapi::SO_LINGER, value)?;
+        self.abortive_close.store(value.l_onoff != 0 && value.l_linger == 0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    ///Retrieves linger behaviour on close.
+    ///
+    ///Wraps `SO_LINGER`.
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        self.get_opt_raw::<winapi::linger>(winapi::SOL_SOCKET, winapi::SO_LINGER).map(|value| match value.l_onoff {
+            0 => None,
+            _ => Some(Duration::from_secs(value.l_linger as u64))
+        })
+    }
+
+    ///Makes socket to be closed abortively.
+    ///
+    ///Shortcut for `set_linger(Some(Duration::from_secs(0)))`.
+    ///
+    ///On close or `Drop` peer receives `RST` instead of `FIN` and all pending data is discarded.
+    pub fn set_abortive_close(&self) -> io::Result<()> {
+        self.set_linger(Some(Duration::from_secs(0)))
+    }
+
+    ///Sets whether address can be re-used by `bind` while previous socket is lingering in `TIME_WAIT`.
+    ///
+    ///Wraps `SO_REUSEADDR`. It must be set before `bind`.
+    ///
+    ///Note: on Windows it also allows to steal address from another socket that is bound to it.
+    pub fn set_reuse_address(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(winapi::SOL_SOCKET, winapi::SO_REUSEADDR, value as c_int)
+    }
+
+    ///Retrieves whether address can be re-used by `bind`.
+    ///
+    ///Wraps `SO_REUSEADDR`.
+    pub fn reuse_address(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(winapi::SOL_SOCKET, winapi::SO_REUSEADDR).map(|value| value != 0)
+    }
+
+    ///Sets whether datagrams can be sent to broadcast addresses.
+    ///
+    ///Wraps `SO_BROADCAST`.
+    pub fn set_broadcast(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(winapi::SOL_SOCKET, winapi::SO_BROADCAST, value as c_int)
+    }
+
+    ///Retrieves whether datagrams can be sent to broadcast addresses.
+    ///
+    ///Wraps `SO_BROADCAST`.
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(winapi::SOL_SOCKET, winapi::SO_BROADCAST).map(|value| value != 0)
+    }
+
+    ///Sets whether IPv6 socket is restricted to IPv6 communication only.
+    ///
+    ///Wraps `IPV6_V6ONLY`. It must be set before `bind`.
+    ///
+    ///When disabled, socket also accepts IPv4 traffic using IPv4-mapped addresses.
+    pub fn set_only_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_V6ONLY, value as c_int)
+    }
+
+    ///Retrieves whether IPv6 socket is restricted to IPv6 communication only.
+    ///
+    ///Wraps `IPV6_V6ONLY`.
+    pub fn only_v6(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_V6ONLY).map(|value| value != 0)
+    }
+
+    ///Binds IPv6 socket to all interfaces for both IPv6 and IPv4 traffic.
+    ///
+    ///Disables `IPV6_V6ONLY` and binds to `[::]:port`.
+    ///
+    ///IPv4 peers are reported by `accept` and `recv_from` as plain IPv4 addresses
+    ///instead of IPv4-mapped ones.
+    pub fn bind_dual_stack(&self, port: u16) -> Result<(), SocketError> {
+        self.set_only_v6(false).operation(Operation::SetOpt)?;
+        self.bind(&net::SocketAddr::V6(net::SocketAddrV6::new(net::Ipv6Addr::UNSPECIFIED, port, 0, 0)))
+    }
+
+    ///Sets time-to-live of outgoing IPv4 packets.
+    ///
+    ///Wraps `IP_TTL`.
+    pub fn set_ttl(&self, ttl: u8) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_TTL, ttl as c_int)
+    }
+
+    ///Returns time-to-live of outgoing IPv4 packets.
+    pub fn ttl(&self) -> io::Result<u8> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IP as c_int, winapi::IP_TTL).map(|ttl| ttl as u8)
+    }
+
+    ///Sets hop limit of outgoing unicast IPv6 packets.
+    ///
+    ///Wraps `IPV6_UNICAST_HOPS`.
+    pub fn set_unicast_hops_v6(&self, hops: u8) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_UNICAST_HOPS, hops as c_int)
+    }
+
+    ///Returns hop limit of outgoing unicast IPv6 packets.
+    pub fn unicast_hops_v6(&self) -> io::Result<u8> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_UNICAST_HOPS).map(|hops| hops as u8)
+    }
+
+    ///Sets type of service field of outgoing IPv4 packets.
+    ///
+    ///Wraps `IP_TOS`. Note: Windows may ignore it unless permitted by QoS policy.
+    pub fn set_tos(&self, tos: TrafficClass) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_TOS, tos.raw() as c_int)
+    }
+
+    ///Returns type of service field of outgoing IPv4 packets.
+    pub fn tos(&self) -> io::Result<TrafficClass> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IP as c_int, winapi::IP_TOS).map(|tos| TrafficClass::from_raw(tos as u8))
+    }
+
+    ///Sets traffic class field of outgoing IPv6 packets.
+    ///
+    ///Wraps `IPV6_TCLASS`. Note: Windows may ignore it unless permitted by QoS policy.
+    pub fn set_tclass_v6(&self, tclass: TrafficClass) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_TCLASS, tclass.raw() as c_int)
+    }
+
+    ///Returns traffic class field of outgoing IPv6 packets.
+    pub fn tclass_v6(&self) -> io::Result<TrafficClass> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_TCLASS).map(|tclass| TrafficClass::from_raw(tclass as u8))
+    }
+
+    ///Joins IPv4 multicast group on interface with specified address.
+    ///
+    ///Wraps `IP_ADD_MEMBERSHIP`. Unspecified address lets system choose interface.
+    pub fn join_multicast_v4(&self, group: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = winapi::IP_MREQ {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_interface: ipv4_to_raw(interface)
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_ADD_MEMBERSHIP, mreq)
+    }
+
+    ///Leaves IPv4 multicast group joined by `join_multicast_v4`.
+    ///
+    ///Wraps `IP_DROP_MEMBERSHIP`.
+    pub fn leave_multicast_v4(&self, group: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = winapi::IP_MREQ {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_interface: ipv4_to_raw(interface)
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_DROP_MEMBERSHIP, mreq)
+    }
+
+    ///Joins IPv4 multicast group on interface with specified index.
+    ///
+    ///Wraps `IP_ADD_MEMBERSHIP` with index passed in place of address. Zero index lets system choose interface.
+    pub fn join_multicast_v4_index(&self, group: &net::Ipv4Addr, interface: u32) -> io::Result<()> {
+        let mreq = winapi::IP_MREQ {
+            imr_multiaddr: ipv4_to_raw(group),
+            //System treats addresses within 0.0.0.0/8 as interface indices.
+            imr_interface: ipv4_to_raw(&net::Ipv4Addr::from(interface))
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_ADD_MEMBERSHIP, mreq)
+    }
+
+    ///Leaves IPv4 multicast group joined by `join_multicast_v4_index`.
+    pub fn leave_multicast_v4_index(&self, group: &net::Ipv4Addr, interface: u32) -> io::Result<()> {
+        let mreq = winapi::IP_MREQ {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_interface: ipv4_to_raw(&net::Ipv4Addr::from(interface))
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_DROP_MEMBERSHIP, mreq)
+    }
+
+    ///Joins IPv4 multicast group, receiving only datagrams sent from `source`.
+    ///
+    ///Wraps `IP_ADD_SOURCE_MEMBERSHIP`. Interface is specified by its address,
+    ///unspecified address lets system choose interface.
+    pub fn join_source_multicast_v4(&self, group: &net::Ipv4Addr, source: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = winapi::IP_MREQ_SOURCE {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_sourceaddr: ipv4_to_raw(source),
+            imr_interface: ipv4_to_raw(interface)
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_ADD_SOURCE_MEMBERSHIP, mreq)
+    }
+
+    ///Leaves IPv4 source-specific multicast group joined by `join_source_multicast_v4`.
+    pub fn leave_source_multicast_v4(&self, group: &net::Ipv4Addr, source: &net::Ipv4Addr, interface: &net::Ipv4Addr) -> io::Result<()> {
+        let mreq = winapi::IP_MREQ_SOURCE {
+            imr_multiaddr: ipv4_to_raw(group),
+            imr_sourceaddr: ipv4_to_raw(source),
+            imr_interface: ipv4_to_raw(interface)
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_DROP_SOURCE_MEMBERSHIP, mreq)
+    }
+
+    ///Joins IPv6 multicast group on interface with specified index.
+    ///
+    ///Wraps `IPV6_JOIN_GROUP`. Zero index lets system choose interface.
+    pub fn join_multicast_v6(&self, group: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        let mreq = winapi::IPV6_MREQ {
+            ipv6mr_multiaddr: ipv6_to_raw(group),
+            ipv6mr_interface: interface
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_JOIN_GROUP, mreq)
+    }
+
+    ///Leaves IPv6 multicast group joined by `join_multicast_v6`.
+    ///
+    ///Wraps `IPV6_LEAVE_GROUP`.
+    pub fn leave_multicast_v6(&self, group: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        let mreq = winapi::IPV6_MREQ {
+            ipv6mr_multiaddr: ipv6_to_raw(group),
+            ipv6mr_interface: interface
+        };
+
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_LEAVE_GROUP, mreq)
+    }
+
+    ///Joins IPv6 multicast group, receiving only datagrams sent from `source`.
+    ///
+    ///Wraps `MCAST_JOIN_SOURCE_GROUP`. Zero index lets system choose interface.
+    pub fn join_source_multicast_v6(&self, group: &net::Ipv6Addr, source: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::MCAST_JOIN_SOURCE_GROUP, group_source_req_v6(group, source, interface))
+    }
+
+    ///Leaves IPv6 source-specific multicast group joined by `join_source_multicast_v6`.
+    ///
+    ///Wraps `MCAST_LEAVE_SOURCE_GROUP`.
+    pub fn leave_source_multicast_v6(&self, group: &net::Ipv6Addr, source: &net::Ipv6Addr, interface: u32) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::MCAST_LEAVE_SOURCE_GROUP, group_source_req_v6(group, source, interface))
+    }
+
+    ///Sets address of interface to send IPv4 multicast datagrams from.
+    ///
+    ///Wraps `IP_MULTICAST_IF`. Unspecified address restores default.
+    pub fn set_multicast_if_v4(&self, interface: &net::Ipv4Addr) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_MULTICAST_IF, ipv4_to_raw(interface))
+    }
+
+    ///Returns address of interface to send IPv4 multicast datagrams from.
+    pub fn multicast_if_v4(&self) -> io::Result<net::Ipv4Addr> {
+        self.get_opt_raw::<winapi::in_addr>(winapi::IPPROTO_IP as c_int, winapi::IP_MULTICAST_IF).map(|addr| unsafe {
+            net::Ipv4Addr::from(u32::from_be(*addr.S_un.S_addr()))
+        })
+    }
+
+    ///Sets index of interface to send IPv6 multicast datagrams from.
+    ///
+    ///Wraps `IPV6_MULTICAST_IF`. Zero index restores default.
+    pub fn set_multicast_if_v6(&self, interface: u32) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_MULTICAST_IF, interface as c_int)
+    }
+
+    ///Returns index of interface to send IPv6 multicast datagrams from.
+    pub fn multicast_if_v6(&self) -> io::Result<u32> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_MULTICAST_IF).map(|interface| interface as u32)
+    }
+
+    ///Sets time-to-live of outgoing IPv4 multicast datagrams.
+    ///
+    ///Wraps `IP_MULTICAST_TTL`. Default of one keeps datagrams within local network.
+    pub fn set_multicast_ttl_v4(&self, ttl: u8) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_MULTICAST_TTL, ttl as c_int)
+    }
+
+    ///Returns time-to-live of outgoing IPv4 multicast datagrams.
+    pub fn multicast_ttl_v4(&self) -> io::Result<u8> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IP as c_int, winapi::IP_MULTICAST_TTL).map(|ttl| ttl as u8)
+    }
+
+    ///Sets hop limit of outgoing IPv6 multicast datagrams.
+    ///
+    ///Wraps `IPV6_MULTICAST_HOPS`.
+    pub fn set_multicast_hops_v6(&self, hops: u8) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_MULTICAST_HOPS, hops as c_int)
+    }
+
+    ///Returns hop limit of outgoing IPv6 multicast datagrams.
+    pub fn multicast_hops_v6(&self) -> io::Result<u8> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_MULTICAST_HOPS).map(|hops| hops as u8)
+    }
+
+    ///Sets whether sent IPv4 multicast datagrams are looped back to local sockets.
+    ///
+    ///Wraps `IP_MULTICAST_LOOP`.
+    pub fn set_multicast_loop_v4(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IP as c_int, winapi::IP_MULTICAST_LOOP, value as c_int)
+    }
+
+    ///Returns whether sent IPv4 multicast datagrams are looped back to local sockets.
+    pub fn multicast_loop_v4(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IP as c_int, winapi::IP_MULTICAST_LOOP).map(|value| value != 0)
+    }
+
+    ///Sets whether sent IPv6 multicast datagrams are looped back to local sockets.
+    ///
+    ///Wraps `IPV6_MULTICAST_LOOP`.
+    pub fn set_multicast_loop_v6(&self, value: bool) -> io::Result<()> {
+        self.set_opt_raw(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_MULTICAST_LOOP, value as c_int)
+    }
+
+    ///Returns whether sent IPv6 multicast datagrams are looped back to local sockets.
+    pub fn multicast_loop_v6(&self) -> io::Result<bool> {
+        self.get_opt_raw::<c_int>(winapi::IPPROTO_IPV6 as c_int, winapi::IPV6_MULTICAST_LOOP).map(|value| value != 0)
+    }
+
     ///Stops receive and/or send over socket.
-    pub fn shutdown(&self, direction: ShutdownType) -> io::Result<()> {
+    pub fn shutdown(&self, direction: ShutdownType) -> Result<(), SocketError> {
         unsafe {
             match winapi::shutdown(self.inner, direction.into()) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Shutdown))
             }
         }
     }
@@ -514,25 +1371,311 @@ impl Socket {
     ///
     ///Note: on `Drop` socket will be closed on its own.
     ///There is no need to close it explicitly.
-    pub fn close(&self) -> io::Result<()> {
+    pub fn close(&self) -> Result<(), SocketError> {
         unsafe {
             match winapi::closesocket(self.inner) {
                 0 => Ok(()),
-                _ => Err(io::Error::last_os_error())
+                _ => Err(SocketError::last_os_error(Operation::Close))
+            }
+        }
+    }
+}
+
+///Views initialised buffer as uninitialised one.
+#[inline]
+fn as_uninit_mut(buf: &mut [u8]) -> &mut [MaybeUninit<u8>] {
+    //Safe as `MaybeUninit<u8>` has same layout and callee never writes uninitialised bytes.
+    unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) }
+}
+
+///Classifies OS error code.
+pub(crate) fn classify_os_error(code: c_int) -> ErrorClass {
+    const TRANSIENT: &[winapi::DWORD] = &[winapi::WSAEWOULDBLOCK, winapi::WSAEINTR, winapi::WSAENOBUFS, winapi::WSAEINPROGRESS, winapi::WSAEALREADY, winapi::WSAETIMEDOUT];
+    const PEER_CLOSED: &[winapi::DWORD] = &[winapi::WSAECONNRESET, winapi::WSAECONNABORTED, winapi::WSAECONNREFUSED, winapi::WSAENOTCONN, winapi::WSAENETRESET, winapi::WSAEDISCON, winapi::WSAESHUTDOWN];
+    const ADDRESS: &[winapi::DWORD] = &[winapi::WSAEADDRINUSE, winapi::WSAEADDRNOTAVAIL, winapi::WSAEAFNOSUPPORT, winapi::WSAENETUNREACH, winapi::WSAEHOSTUNREACH, winapi::WSAENETDOWN, winapi::WSAEHOSTDOWN, winapi::WSAEDESTADDRREQ];
+    const PERMISSION: &[winapi::DWORD] = &[winapi::WSAEACCES];
+    const PROGRAMMER: &[winapi::DWORD] = &[winapi::WSAEBADF, winapi::WSAENOTSOCK, winapi::WSAEINVAL, winapi::WSAEFAULT, winapi::WSAEISCONN, winapi::WSAEOPNOTSUPP, winapi::WSAENOPROTOOPT, winapi::WSAEPROTONOSUPPORT, winapi::WSAEPROTOTYPE, winapi::WSAESOCKTNOSUPPORT, winapi::WSAEMSGSIZE];
+
+    let code = code as winapi::DWORD;
+
+    if TRANSIENT.contains(&code) {
+        ErrorClass::Transient
+    } else if PEER_CLOSED.contains(&code) {
+        ErrorClass::PeerClosed
+    } else if ADDRESS.contains(&code) {
+        ErrorClass::Address
+    } else if PERMISSION.contains(&code) {
+        ErrorClass::Permission
+    } else if PROGRAMMER.contains(&code) {
+        ErrorClass::Programmer
+    } else {
+        ErrorClass::Other
+    }
+}
+
+#[inline]
+fn is_connect_in_progress(code: c_int) -> bool {
+    code == winapi::WSAEWOULDBLOCK as i32 || code == winapi::WSAEINPROGRESS as i32 || code == winapi::WSAEALREADY as i32
+}
+
+///Outcome of `Socket::connect_nonblocking`.
+pub enum ConnectState<'a> {
+    ///Connection is established.
+    Connected,
+    ///Connection is being established.
+    InProgress(PendingConnect<'a>)
+}
+
+///Connection that is being established in non-blocking mode.
+pub struct PendingConnect<'a> {
+    socket: &'a Socket
+}
+
+impl<'a> PendingConnect<'a> {
+    ///Returns socket that is being connected.
+    pub fn socket(&self) -> &'a Socket {
+        self.socket
+    }
+
+    ///Checks whether connection is complete without blocking.
+    ///
+    ///Returns `true` once connection is established and `false` while it is still in progress.
+    ///
+    ///Failure to connect is reported as error taken from `SO_ERROR`.
+    ///As it is cleared once taken, subsequent polls report `NotConnected` error.
+    pub fn poll_complete(&self) -> Result<bool, SocketError> {
+        self.poll_complete_raw().operation(Operation::Connect)
+    }
+
+    fn poll_complete_raw(&self) -> io::Result<bool> {
+        match select_raw(&[], &[self.socket], &[self.socket], Some(0))? {
+            0 => Ok(false),
+            _ => match self.socket.take_error()? {
+                Some(error) => Err(error),
+                //Connection is confirmed by presence of peer.
+                None => unsafe {
+                    let mut storage: winapi::SOCKADDR_STORAGE_LH = mem::zeroed();
+                    let mut len = mem::size_of_val(&storage) as c_int;
+
+                    match winapi::getpeername(self.socket.inner, &mut storage as *mut _ as *mut _, &mut len) {
+                        winapi::SOCKET_ERROR => Err(io::Error::last_os_error()),
+                        _ => Ok(true)
+                    }
+                }
             }
         }
     }
 }
 
-fn get_raw_addr(addr: &net::SocketAddr) -> (*const winapi::SOCKADDR, c_int) {
-    match *addr {
-        net::SocketAddr::V4(ref a) => {
-            (a as *const _ as *const _, mem::size_of_val(a) as c_int)
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+///Explicit Congestion Notification codepoint.
+pub enum Ecn {
+    ///Transport is not ECN capable.
+    NotEct = 0,
+    ///ECN capable transport, `ECT(1)`.
+    Ect1 = 1,
+    ///ECN capable transport, `ECT(0)`.
+    Ect0 = 2,
+    ///Congestion experienced.
+    Ce = 3
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+///IPv4 type of service or IPv6 traffic class field.
+///
+///Consists of DSCP in upper six bits and ECN in lower two bits.
+pub struct TrafficClass(u8);
+
+impl TrafficClass {
+    ///Creates field from DSCP, of which only lower six bits are used, and ECN.
+    pub fn new(dscp: u8, ecn: Ecn) -> TrafficClass {
+        TrafficClass((dscp << 2) | ecn as u8)
+    }
+
+    ///Creates field from its raw value.
+    pub fn from_raw(raw: u8) -> TrafficClass {
+        TrafficClass(raw)
+    }
+
+    ///Returns raw value of field.
+    pub fn raw(&self) -> u8 {
+        self.0
+    }
+
+    ///Returns Differentiated Services Code Point.
+    pub fn dscp(&self) -> u8 {
+        self.0 >> 2
+    }
+
+    ///Returns Explicit Congestion Notification codepoint.
+    pub fn ecn(&self) -> Ecn {
+        match self.0 & 0b11 {
+            0 => Ecn::NotEct,
+            1 => Ecn::Ect1,
+            2 => Ecn::Ect0,
+            _ => Ecn::Ce
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+///IPv4 broadcast address of network interface.
+pub struct InterfaceBroadcast {
+    ///Name of interface.
+    pub name: String,
+    ///Address assigned to interface.
+    pub addr: net::Ipv4Addr,
+    ///Broadcast address of the subnet.
+    pub broadcast: net::Ipv4Addr
+}
+
+///Lists IPv4 broadcast addresses of network interfaces.
+///
+///Wraps `GetAdaptersAddresses`. Broadcast address is derived from on-link prefix length,
+///loopback, point-to-point and tunnel interfaces as well as `/31` and `/32` subnets are omitted.
+///Interface with multiple IPv4 addresses is listed once for each of them, under its friendly name.
+pub fn interface_broadcasts() -> io::Result<Vec<InterfaceBroadcast>> {
+    let flags = winapi::GAA_FLAG_SKIP_ANYCAST | winapi::GAA_FLAG_SKIP_MULTICAST | winapi::GAA_FLAG_SKIP_DNS_SERVER;
+    //Aligned for IP_ADAPTER_ADDRESSES_LH, grown to size requested by system.
+    let mut buffer: Vec<u64> = Vec::new();
+    let mut size: winapi::ULONG = 16 * 1024;
+
+    loop {
+        buffer.resize((size as usize + 7) / 8, 0);
+
+        match unsafe { winapi::GetAdaptersAddresses(winapi::AF_INET as winapi::ULONG, flags, ptr::null_mut(), buffer.as_mut_ptr() as *mut _, &mut size) } {
+            winapi::ERROR_SUCCESS => break,
+            winapi::ERROR_BUFFER_OVERFLOW => continue,
+            //No adapter has IPv4 address.
+            winapi::ERROR_NO_DATA => return Ok(Vec::new()),
+            error => return Err(io::Error::from_raw_os_error(error as i32))
+        }
+    }
+
+    let mut result = Vec::new();
+    let mut adapter = buffer.as_ptr() as *const winapi::IP_ADAPTER_ADDRESSES_LH;
+
+    while let Some(raw) = unsafe { adapter.as_ref() } {
+        adapter = raw.Next;
+
+        match raw.IfType {
+            winapi::IF_TYPE_SOFTWARE_LOOPBACK | winapi::IF_TYPE_PPP | winapi::IF_TYPE_TUNNEL => continue,
+            _ => ()
         }
-        net::SocketAddr::V6(ref a) => {
-            (a as *const _ as *const _, mem::size_of_val(a) as c_int)
+
+        let name = unsafe { wide_to_string(raw.FriendlyName) };
+        let mut unicast = raw.FirstUnicastAddress;
+
+        while let Some(address) = unsafe { unicast.as_ref() } {
+            unicast = address.Next;
+
+            let addr = match unsafe { sockaddr_to_ipv4(address.Address.lpSockaddr) } {
+                Some(addr) if address.OnLinkPrefixLength < 31 => addr,
+                _ => continue
+            };
+            let host_mask = u32::MAX >> address.OnLinkPrefixLength;
+
+            result.push(InterfaceBroadcast {
+                name: name.clone(),
+                addr,
+                broadcast: net::Ipv4Addr::from(u32::from(addr) | host_mask)
+            });
         }
     }
+
+    Ok(result)
+}
+
+///Reads IPv4 address from possibly null `SOCKADDR`.
+unsafe fn sockaddr_to_ipv4(addr: *const winapi::SOCKADDR) -> Option<net::Ipv4Addr> {
+    match addr.as_ref() {
+        Some(raw) if raw.sa_family as c_int == winapi::AF_INET => {
+            let raw = &*(addr as *const winapi::SOCKADDR_IN);
+            Some(net::Ipv4Addr::from(u32::from_be(*raw.sin_addr.S_un.S_addr())))
+        },
+        _ => None
+    }
+}
+
+///Converts possibly null wide string terminated by zero.
+unsafe fn wide_to_string(raw: *const winapi::WCHAR) -> String {
+    if raw.is_null() {
+        return String::new();
+    }
+
+    let len = (0..).take_while(|&idx| *raw.offset(idx) != 0).count();
+    String::from_utf16_lossy(slice::from_raw_parts(raw, len))
+}
+
+#[inline]
+fn ipv4_to_raw(addr: &net::Ipv4Addr) -> winapi::in_addr {
+    let mut raw: winapi::in_addr = unsafe { mem::zeroed() };
+    unsafe {
+        *raw.S_un.S_addr_mut() = u32::from(*addr).to_be();
+    }
+    raw
+}
+
+#[inline]
+fn ipv6_to_raw(addr: &net::Ipv6Addr) -> winapi::in6_addr {
+    let mut raw: winapi::in6_addr = unsafe { mem::zeroed() };
+    unsafe {
+        *raw.u.Byte_mut() = addr.octets();
+    }
+    raw
+}
+
+fn group_source_req_v6(group: &net::Ipv6Addr, source: &net::Ipv6Addr, interface: u32) -> winapi::GROUP_SOURCE_REQ {
+    let group = net::SocketAddr::V6(net::SocketAddrV6::new(*group, 0, 0, 0));
+    let source = net::SocketAddr::V6(net::SocketAddrV6::new(*source, 0, 0, 0));
+
+    winapi::GROUP_SOURCE_REQ {
+        gsr_interface: interface,
+        gsr_group: get_raw_addr(&group, winapi::AF_INET6).0,
+        gsr_source: get_raw_addr(&source, winapi::AF_INET6).0
+    }
+}
+
+///Converts address to raw one suitable for socket of specified `family`.
+///
+///IPv4 addresses are converted to IPv4-mapped ones for `AF_INET6` sockets, so that
+///peers reported by dual-stack sockets can be passed back to them.
+fn get_raw_addr(addr: &net::SocketAddr, family: c_int) -> (winapi::SOCKADDR_STORAGE_LH, c_int) {
+    //Layout of std's addresses is not guaranteed to match C structs so we fill storage by hand.
+    let mut storage: winapi::SOCKADDR_STORAGE_LH = unsafe { mem::zeroed() };
+
+    let mapped;
+    let addr = match *addr {
+        net::SocketAddr::V4(ref a) if family == winapi::AF_INET6 => {
+            mapped = net::SocketAddr::V6(net::SocketAddrV6::new(a.ip().to_ipv6_mapped(), a.port(), 0, 0));
+            &mapped
+        },
+        _ => addr
+    };
+
+    let len = match *addr {
+        net::SocketAddr::V4(ref a) => unsafe {
+            let raw = &mut *(&mut storage as *mut _ as *mut winapi::SOCKADDR_IN);
+            raw.sin_family = winapi::AF_INET as winapi::ADDRESS_FAMILY;
+            raw.sin_port = a.port().to_be();
+            *raw.sin_addr.S_un.S_addr_mut() = u32::from(*a.ip()).to_be();
+
+            mem::size_of::<winapi::SOCKADDR_IN>()
+        },
+        net::SocketAddr::V6(ref a) => unsafe {
+            let raw = &mut *(&mut storage as *mut _ as *mut winapi::SOCKADDR_IN6_LH);
+            raw.sin6_family = winapi::AF_INET6 as winapi::ADDRESS_FAMILY;
+            raw.sin6_port = a.port().to_be();
+            raw.sin6_flowinfo = a.flowinfo();
+            *raw.sin6_addr.u.Byte_mut() = a.ip().octets();
+            *raw.u.sin6_scope_id_mut() = a.scope_id();
+
+            mem::size_of::<winapi::SOCKADDR_IN6_LH>()
+        }
+    };
+
+    (storage, len as c_int)
 }
 
 fn sockaddr_to_addr(storage: &winapi::SOCKADDR_STORAGE_LH, len: c_int) -> io::Result<net::SocketAddr> {
@@ -566,9 +1709,23 @@ fn sockaddr_to_addr(storage: &winapi::SOCKADDR_STORAGE_LH, len: c_int) -> io::Re
     }
 }
 
+///Converts address of peer, unwrapping IPv4-mapped addresses of dual-stack sockets.
+fn sockaddr_to_peer_addr(storage: &winapi::SOCKADDR_STORAGE_LH, len: c_int) -> io::Result<net::SocketAddr> {
+    match sockaddr_to_addr(storage, len)? {
+        net::SocketAddr::V6(addr) => match addr.ip().to_ipv4_mapped() {
+            Some(ip) => Ok(net::SocketAddr::V4(net::SocketAddrV4::new(ip, addr.port()))),
+            None => Ok(net::SocketAddr::V6(addr))
+        },
+        addr => Ok(addr)
+    }
+}
+
 impl Drop for Socket {
     fn drop(&mut self) {
-        let _ = self.shutdown(ShutdownType::Both);
+        //Orderly shutdown would send FIN before reset so skip it for abortive close.
+        if !self.abortive_close.load(Ordering::Relaxed) {
+            let _ = self.shutdown(ShutdownType::Both);
+        }
         let _ = self.close();
     }
 }
@@ -587,7 +1744,12 @@ impl AsRawSocket for Socket {
 
 impl FromRawSocket for Socket {
     unsafe fn from_raw_socket(sock: winapi::STD_SOCKET) -> Self {
-        Socket {inner: sock as winapi::SOCKET}
+        Socket {
+            inner: sock as winapi::SOCKET,
+            abortive_close: AtomicBool::new(false),
+            blocking: AtomicBool::new(true),
+            family: AtomicI32::new(winapi::AF_UNSPEC)
+        }
     }
 }
 
@@ -599,11 +1761,53 @@ impl IntoRawSocket for Socket {
     }
 }
 
+///Returns deadline that is `timeout` away, `None` if it cannot be represented.
+fn deadline_after(timeout: Duration) -> io::Result<Option<Instant>> {
+    if timeout == Duration::from_secs(0) {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "Zero timeout is not allowed."))
+    } else {
+        Ok(Instant::now().checked_add(timeout))
+    }
+}
+
+#[inline]
+fn duration_to_ms_ceil(duration: Duration) -> u64 {
+    let ms = duration.as_secs().saturating_mul(1000).saturating_add(duration.subsec_nanos() as u64 / 1_000_000);
+
+    match duration.subsec_nanos() % 1_000_000 {
+        0 => ms,
+        _ => ms.saturating_add(1)
+    }
+}
+
 #[inline]
-fn ms_to_timeval(timeout_ms: u64) -> winapi::timeval {
+fn duration_to_timeval(duration: Duration) -> winapi::timeval {
     winapi::timeval {
-        tv_sec: timeout_ms as c_long / 1000,
-        tv_usec: (timeout_ms as c_long % 1000) * 1000
+        tv_sec: cmp::min(duration.as_secs(), c_long::max_value() as u64) as c_long,
+        tv_usec: duration.subsec_micros() as c_long
+    }
+}
+
+fn timeout_to_ms(timeout: Option<Duration>) -> io::Result<winapi::DWORD> {
+    match timeout {
+        Some(timeout) => {
+            let ms = timeout.as_secs().saturating_mul(1000).saturating_add(timeout.subsec_nanos() as u64 / 1_000_000);
+
+            match cmp::min(ms, winapi::DWORD::max_value() as u64) as winapi::DWORD {
+                0 if timeout.subsec_nanos() == 0 => Err(io::Error::new(io::ErrorKind::InvalidInput, "Zero timeout is not allowed.")),
+                //Round up sub-millisecond timeouts as otherwise they would become infinite.
+                0 => Ok(1),
+                ms => Ok(ms)
+            }
+        },
+        None => Ok(0)
+    }
+}
+
+fn ms_to_timeout(timeout: winapi::DWORD) -> Option<Duration> {
+    match timeout {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64))
     }
 }
 
@@ -633,7 +1837,11 @@ fn sockets_to_fd_set(sockets: &[&Socket]) -> winapi::fd_set {
 ///## Warning:
 ///
 ///It is invalid to pass all sets of descriptors empty on Windows.
-pub fn select(read_fds: &[&Socket], write_fds: &[&Socket], except_fds: &[&Socket], timeout_ms: Option<u64>) -> io::Result<c_int> {
+pub fn select(read_fds: &[&Socket], write_fds: &[&Socket], except_fds: &[&Socket], timeout_ms: Option<u64>) -> Result<c_int, SocketError> {
+    select_raw(read_fds, write_fds, except_fds, timeout_ms).operation(Operation::Select)
+}
+
+fn select_raw(read_fds: &[&Socket], write_fds: &[&Socket], except_fds: &[&Socket], timeout_ms: Option<u64>) -> io::Result<c_int> {
     let mut raw_read_fds = sockets_to_fd_set(read_fds);
     let mut raw_write_fds = sockets_to_fd_set(write_fds);
     let mut raw_except_fds = sockets_to_fd_set(except_fds);
@@ -643,7 +1851,7 @@ pub fn select(read_fds: &[&Socket], write_fds: &[&Socket], except_fds: &[&Socket
                              if read_fds.len() > 0 { &mut raw_read_fds } else { ptr::null_mut() },
                              if write_fds.len() > 0 { &mut raw_write_fds } else { ptr::null_mut() },
                              if except_fds.len() > 0 { &mut raw_except_fds } else { ptr::null_mut() },
-                             if let Some(timeout_ms) = timeout_ms { &ms_to_timeval(timeout_ms) } else { ptr::null() } ) {
+                             if let Some(timeout_ms) = timeout_ms { &duration_to_timeval(Duration::from_millis(timeout_ms)) } else { ptr::null() } ) {
             winapi::SOCKET_ERROR => Err(io::Error::last_os_error()),
             result @ _ => Ok(result)
 
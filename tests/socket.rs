@@ -175,7 +175,7 @@ fn socket_test_tcp6() {
 
         // Check whether the `NON_INHERITABLE` flag worked
         let result = result_socket.get_inheritable();
-        assert!(result.is_ok() && result.unwrap() == false);
+        assert!(result.is_ok() && !result.unwrap());
 
 		// Check whether the `NON_BLOCKING` flag worked
         let mut buf = [0; 10];
@@ -234,6 +234,1333 @@ fn socket_test_options() {
     assert!(socket.set_inheritable(true).is_ok());
 }
 
+#[test]
+fn socket_test_timeouts_and_buffers() {
+    let socket = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+
+    assert_eq!(socket.read_timeout().unwrap(), None);
+    assert_eq!(socket.write_timeout().unwrap(), None);
+
+    let timeout = time::Duration::from_millis(1500);
+    assert!(socket.set_read_timeout(Some(timeout)).is_ok());
+    assert_eq!(socket.read_timeout().unwrap(), Some(timeout));
+    assert!(socket.set_write_timeout(Some(timeout)).is_ok());
+    assert_eq!(socket.write_timeout().unwrap(), Some(timeout));
+
+    let result = socket.set_read_timeout(Some(time::Duration::from_secs(0)));
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    assert!(socket.set_read_timeout(None).is_ok());
+    assert_eq!(socket.read_timeout().unwrap(), None);
+
+    let now = time::Instant::now();
+    assert!(socket.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(socket.set_read_timeout(Some(time::Duration::from_millis(100))).is_ok());
+    let mut buf = [0; 10];
+    let result = socket.recv(&mut buf, 0);
+    assert!(result.is_err());
+    assert!(now.elapsed() >= time::Duration::from_millis(100));
+
+    //Default size is reported as is.
+    #[cfg(target_os = "linux")]
+    {
+        let read_sysctl = |path: &str| std::fs::read_to_string(path).unwrap().trim().parse::<usize>().unwrap();
+        let socket = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+        assert_eq!(socket.recv_buffer_size().unwrap(), read_sysctl("/proc/sys/net/core/rmem_default"));
+        assert_eq!(socket.send_buffer_size().unwrap(), read_sysctl("/proc/sys/net/core/wmem_default"));
+
+        //Tiny size is raised to kernel's minimum instead of being doubled.
+        assert!(socket.set_recv_buffer_size(1).is_ok());
+        assert!(socket.recv_buffer_size().unwrap() >= 2048);
+    }
+
+    //Size is set within sane default limits so that kernel doesn't clamp it.
+    let size = 32 * 1024;
+    //Linux doubles requested size to account for bookkeeping overhead.
+    let expected = if cfg!(any(target_os = "linux", target_os = "android")) { 2 * size } else { size };
+    assert!(socket.set_recv_buffer_size(size).is_ok());
+    assert_eq!(socket.recv_buffer_size().unwrap(), expected);
+    assert!(socket.set_send_buffer_size(size).is_ok());
+    assert_eq!(socket.send_buffer_size().unwrap(), expected);
+}
+
+#[test]
+fn socket_test_linger() {
+    let family = Family::IPv4;
+    let ty = Type::STREAM;
+    let proto = Protocol::TCP;
+
+    let server = Socket::new(family, ty, proto).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(server.listen(1).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let client = Socket::new(family, ty, proto).unwrap();
+    assert_eq!(client.linger().unwrap(), None);
+    assert!(client.set_linger(Some(time::Duration::from_millis(1500))).is_ok());
+    assert_eq!(client.linger().unwrap(), Some(time::Duration::from_secs(2)));
+    assert!(client.set_linger(None).is_ok());
+    assert_eq!(client.linger().unwrap(), None);
+
+    assert!(client.connect(&server_addr).is_ok());
+    let (accepted, _) = server.accept().unwrap();
+    assert!(accepted.set_abortive_close().is_ok());
+    assert_eq!(accepted.linger().unwrap(), Some(time::Duration::from_secs(0)));
+    drop(accepted);
+
+    let mut buf = [0; 10];
+    let result = client.recv(&mut buf, 0);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::ConnectionReset);
+}
+
+#[test]
+fn socket_test_reuse_and_dual_stack() {
+    let socket = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(!socket.reuse_address().unwrap());
+    assert!(socket.set_reuse_address(true).is_ok());
+    assert!(socket.reuse_address().unwrap());
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    {
+        assert!(!socket.reuse_port().unwrap());
+        assert!(socket.set_reuse_port(true).is_ok());
+        assert!(socket.reuse_port().unwrap());
+    }
+
+    let server = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.set_only_v6(true).is_ok());
+    assert!(server.only_v6().unwrap());
+    assert!(server.bind_dual_stack(0).is_ok());
+    assert!(!server.only_v6().unwrap());
+    let port = server.name().unwrap().port();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let client_addr = client.name().unwrap();
+
+    let data = [1, 2, 3, 4];
+    let server_addr = net::SocketAddr::from_str(&format!("127.0.0.1:{}", port)).unwrap();
+    assert_eq!(client.send_to(&data, &server_addr, 0).unwrap(), data.len());
+
+    let mut buf = [0; 10];
+    let (result_len, result_addr) = server.recv_from(&mut buf, 0).unwrap();
+    assert_eq!(result_len, data.len());
+    assert_eq!(result_addr, client_addr);
+
+    //Unwrapped IPv4 peer is accepted back by dual-stack socket.
+    assert_eq!(server.send_to(&data[..2], &result_addr, 0).unwrap(), 2);
+    assert_eq!(client.recv_from(&mut buf, 0).unwrap(), (2, server_addr));
+    assert!(server.connect(&result_addr).is_ok());
+    assert_eq!(server.send(&data[..3], 0).unwrap(), 3);
+    assert_eq!(client.recv_from(&mut buf, 0).unwrap(), (3, server_addr));
+
+    let listener = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(listener.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(listener.listen(1).is_ok());
+    let listener_addr = listener.name().unwrap();
+
+    let stream = Socket::new(Family::IPv6, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(stream.set_only_v6(false).is_ok());
+    assert!(stream.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(stream.connect(&listener_addr).is_ok());
+    let (_, peer_addr) = listener.accept().unwrap();
+    assert_eq!(peer_addr.ip(), listener_addr.ip());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_reuse_port_group() {
+    let family = Family::IPv4;
+    let ty = Type::DATAGRAM;
+    let proto = Protocol::UDP;
+    let addr = net::SocketAddr::from_str("127.0.0.1:0").unwrap();
+
+    let group = reuse_port_group(family, ty, proto, &addr, 2, Some(ReusePortSteering::SourcePort)).unwrap();
+    assert_eq!(group.len(), 2);
+    let group_addr = group[0].name().unwrap();
+    assert_eq!(group_addr, group[1].name().unwrap());
+
+    let data = [1, 2, 3, 4];
+    let mut buf = [0; 10];
+    let mut received = [0; 2];
+
+    //Ephemeral ports are random so keep sending until every socket received some traffic.
+    for _ in 0..64 {
+        if received.iter().all(|count| *count >= 2) {
+            break;
+        }
+
+        let client = Socket::new(family, ty, proto).unwrap();
+        assert!(client.bind(&addr).is_ok());
+        let client_addr = client.name().unwrap();
+        assert_eq!(client.send_to(&data, &group_addr, 0).unwrap(), data.len());
+
+        let idx = client_addr.port() as usize % group.len();
+        assert!(group[idx].set_read_timeout(Some(time::Duration::from_millis(500))).is_ok());
+        let (result_len, result_addr) = group[idx].recv_from(&mut buf, 0).unwrap();
+        assert_eq!(result_len, data.len());
+        assert_eq!(result_addr, client_addr);
+        received[idx] += 1;
+    }
+
+    assert!(received[0] >= 2);
+    assert!(received[1] >= 2);
+
+    let group = reuse_port_group(family, ty, proto, &addr, 2, Some(ReusePortSteering::Cpu));
+    assert!(group.is_ok());
+
+    let error = ReusePortSteering::SourcePort.program(Family::UNIX, 2).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(ReusePortSteering::SourcePort.program(Family::IPv6, 2).is_ok());
+
+    let error = ReusePortSteering::Cpu.program(Family::IPv4, 0).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    #[cfg(target_pointer_width = "64")]
+    {
+        let error = ReusePortSteering::Cpu.program(Family::IPv4, u32::MAX as usize + 1).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(ReusePortSteering::Cpu.program(Family::IPv4, u32::MAX as usize).is_ok());
+    }
+}
+
+#[test]
+fn socket_test_take_error() {
+    //Grab free port and release it so that nobody listens on it.
+    let server_addr = {
+        let server = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+        assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+        server.name().unwrap()
+    };
+
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(client.take_error().unwrap().is_none());
+
+    assert!(client.set_blocking(false).is_ok());
+    let _ = client.connect(&server_addr);
+    let result = lazy_socket::raw::select(&[], &[&client], &[&client], Some(1000));
+    assert_eq!(result.unwrap(), 1);
+
+    let error = client.take_error().unwrap();
+    assert_eq!(error.unwrap().kind(), std::io::ErrorKind::ConnectionRefused);
+    assert!(client.take_error().unwrap().is_none());
+}
+
+#[test]
+fn socket_test_connect_nonblocking() {
+    let server = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let server_addr = server.name().unwrap();
+
+    //Not listening yet so connection is refused.
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    match client.connect_nonblocking(&server_addr) {
+        Ok(ConnectState::InProgress(pending)) => {
+            assert!(lazy_socket::raw::select(&[], &[&client], &[&client], Some(1000)).is_ok());
+            let error = pending.poll_complete().unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::ConnectionRefused);
+            //Failure is not forgotten once error is taken.
+            let error = pending.poll_complete().unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::NotConnected);
+        },
+        Ok(ConnectState::Connected) => panic!("Connected to closed port"),
+        Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::ConnectionRefused)
+    }
+
+    assert!(server.listen(1).is_ok());
+
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    match client.connect_nonblocking(&server_addr).unwrap() {
+        ConnectState::InProgress(pending) => {
+            assert!(lazy_socket::raw::select(&[], &[&client], &[&client], Some(1000)).is_ok());
+            assert!(pending.poll_complete().unwrap());
+        },
+        ConnectState::Connected => ()
+    }
+
+    //Repeated connect reports established connection.
+    match client.connect_nonblocking(&server_addr).unwrap() {
+        ConnectState::Connected => (),
+        ConnectState::InProgress(_) => panic!("Connection is expected to be established")
+    }
+}
+
+#[test]
+fn socket_test_deadline_operations() {
+    let timeout = time::Duration::from_millis(100);
+    let data = [1, 2, 3, 4];
+
+    let server = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(server.listen(1).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let now = time::Instant::now();
+    let result = server.accept_timeout(timeout);
+    assert_eq!(result.err().unwrap().kind(), std::io::ErrorKind::TimedOut);
+    assert!(now.elapsed() >= timeout);
+
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(client.connect_timeout(&server_addr, time::Duration::from_secs(1)).is_ok());
+    let (accepted, _) = server.accept_timeout(time::Duration::from_secs(1)).unwrap();
+
+    let mut buf = [0; 10];
+    let now = time::Instant::now();
+    let result = accepted.recv_timeout(&mut buf, 0, timeout);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::TimedOut);
+    assert!(now.elapsed() >= timeout);
+
+    assert_eq!(client.send_timeout(&data, 0, timeout).unwrap(), data.len());
+    assert_eq!(accepted.recv_timeout(&mut buf, 0, timeout).unwrap(), data.len());
+    assert_eq!(&buf[..data.len()], data);
+
+    //Unrepresentable deadline means waiting without one.
+    let huge = time::Duration::from_secs(u64::MAX);
+    assert_eq!(client.send_timeout(&data, 0, huge).unwrap(), data.len());
+    assert_eq!(accepted.recv_timeout(&mut buf, 0, huge).unwrap(), data.len());
+
+    //Non-blocking mode is kept.
+    assert!(client.set_blocking(false).is_ok());
+    assert_eq!(client.send_timeout(&data, 0, timeout).unwrap(), data.len());
+    assert_eq!(client.recv(&mut buf, 0).unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+    assert!(client.set_blocking(true).is_ok());
+    assert_eq!(accepted.recv_timeout(&mut buf, 0, timeout).unwrap(), data.len());
+
+    //Original blocking mode is restored.
+    assert!(accepted.set_read_timeout(Some(timeout)).is_ok());
+    let result = accepted.recv(&mut buf, 0);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+    assert!(now.elapsed() >= timeout * 2);
+
+    let result = client.connect_timeout(&server_addr, time::Duration::from_secs(0));
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[cfg(unix)]
+#[test]
+fn socket_test_interrupted_restart() {
+    extern "C" fn handler(_: c_int) {}
+
+    //Install handler without SA_RESTART so that blocking calls fail with EINTR.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler as *const () as libc::sighandler_t;
+        assert_eq!(libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut()), 0);
+    }
+
+    let data = [1, 2, 3, 4];
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let target = unsafe { libc::pthread_self() };
+    let th = thread::spawn(move || {
+        thread::sleep(time::Duration::from_millis(50));
+        assert_eq!(unsafe { libc::pthread_kill(target, libc::SIGUSR1) }, 0);
+        thread::sleep(time::Duration::from_millis(50));
+
+        let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+        assert_eq!(client.send_to(&data, &server_addr, 0).unwrap(), data.len());
+
+        thread::sleep(time::Duration::from_millis(50));
+        assert_eq!(unsafe { libc::pthread_kill(target, libc::SIGUSR1) }, 0);
+    });
+
+    let mut buf = [0; 10];
+    assert_eq!(server.recv(&mut buf, 0).unwrap(), data.len());
+
+    let now = time::Instant::now();
+    let result = lazy_socket::raw::select(&[&server], &[], &[], Some(200));
+    assert_eq!(result.unwrap(), 0);
+    assert!(now.elapsed() >= time::Duration::from_millis(200));
+
+    assert!(th.join().is_ok());
+
+    //Timeout too large for deadline waits without one.
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert_eq!(client.send_to(&data, &server_addr, 0).unwrap(), data.len());
+    assert_eq!(lazy_socket::raw::select(&[&server], &[], &[], Some(u64::MAX)).unwrap(), 1);
+}
+
+#[test]
+fn socket_test_send_all_recv_exact() {
+    let server = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(server.listen(1).is_ok());
+    let server_addr = server.name().unwrap();
+
+    //Large enough to overflow socket buffers so that partial sends happen.
+    let data: Vec<u8> = (0..4 * 1024 * 1024).map(|idx| idx as u8).collect();
+    let expected = data.clone();
+
+    let th = thread::spawn(move || {
+        let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+        assert!(client.connect(&server_addr).is_ok());
+        assert!(client.set_blocking(false).is_ok());
+        assert!(client.send_all(&data, 0, None).is_ok());
+    });
+
+    let (accepted, _) = server.accept().unwrap();
+    let mut buf = vec![0; expected.len()];
+    assert!(accepted.recv_exact(&mut buf, 0, None).is_ok());
+    assert!(buf == expected);
+
+    assert!(th.join().is_ok());
+
+    let result = accepted.recv_exact(&mut buf[..1], 0, None);
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(error.transferred(), 0);
+
+    //Number of received bytes is reported on failure.
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(client.connect(&server_addr).is_ok());
+    let (accepted, _) = server.accept().unwrap();
+    assert!(accepted.set_blocking(false).is_ok());
+    assert!(client.send_all(&[1, 2, 3], 0, None).is_ok());
+
+    let timeout = time::Duration::from_millis(100);
+    let now = time::Instant::now();
+    let error = accepted.recv_exact(&mut buf[..10], 0, Some(timeout)).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    assert_eq!(error.transferred(), 3);
+    assert_eq!(&buf[..3], [1, 2, 3]);
+    assert!(now.elapsed() >= timeout);
+
+    let error = accepted.recv_exact(&mut buf[..10], 0, Some(time::Duration::from_secs(0))).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::WouldBlock);
+    assert_eq!(error.transferred(), 0);
+
+    //Timeout bounds whole call, even if peer keeps trickling data.
+    let trickle = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(trickle.connect(&server_addr).is_ok());
+    let (trickled, _) = server.accept().unwrap();
+    assert!(trickled.set_blocking(false).is_ok());
+    let th = thread::spawn(move || {
+        for byte in 0..10u8 {
+            thread::sleep(time::Duration::from_millis(40));
+            assert!(trickle.send(&[byte], 0).is_ok());
+        }
+    });
+    let now = time::Instant::now();
+    let error = trickled.recv_exact(&mut buf[..10], 0, Some(timeout)).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::TimedOut);
+    assert!(error.transferred() > 0 && error.transferred() < 10);
+    assert!(now.elapsed() < time::Duration::from_millis(300));
+    assert!(th.join().is_ok());
+
+    assert!(client.send_all(&[4, 5], 0, None).is_ok());
+    drop(client);
+    let error = accepted.recv_exact(&mut buf[..10], 0, None).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(error.transferred(), 2);
+}
+
+#[test]
+fn socket_test_msg_flags() {
+    let data = [1, 2, 3, 4];
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert_eq!(client.send_to(&data, &server_addr, MsgFlags::empty()).unwrap(), data.len());
+
+    let mut buf = [0; 10];
+    assert_eq!(server.recv_from(&mut buf, MsgFlags::PEEK).unwrap().0, data.len());
+    assert_eq!(server.recv(&mut buf, MsgFlags::PEEK).unwrap(), data.len());
+    assert_eq!(server.recv(&mut buf, 0).unwrap(), data.len());
+    assert_eq!(&buf[..data.len()], data);
+
+    #[cfg(unix)]
+    {
+        let result = server.recv(&mut buf, MsgFlags::DONTWAIT);
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::WouldBlock);
+        assert_eq!(c_int::from(MsgFlags::PEEK | MsgFlags::DONTWAIT), libc::MSG_PEEK | libc::MSG_DONTWAIT);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_send_no_sigpipe() {
+    use std::io::Write;
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static SIGPIPE_COUNT: AtomicUsize = AtomicUsize::new(0);
+    extern "C" fn handler(_: c_int) {
+        SIGPIPE_COUNT.fetch_add(1, Ordering::SeqCst);
+    }
+
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler as *const () as libc::sighandler_t;
+        assert_eq!(libc::sigaction(libc::SIGPIPE, &action, std::ptr::null_mut()), 0);
+    }
+
+    let data = [1, 2, 3, 4];
+    let server = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(server.listen(1).is_ok());
+    let server_addr = server.name().unwrap();
+
+    //Local shutdown is reported by Linux as EPIPE rather than ESHUTDOWN, so it is an error.
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(client.connect(&server_addr).is_ok());
+    let (_accepted, _) = server.accept().unwrap();
+    assert!(client.shutdown(ShutdownType::Send).is_ok());
+    let result = client.send(&data, 0);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+
+    //Peer closed connection.
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(client.connect(&server_addr).is_ok());
+    drop(server.accept().unwrap());
+    //Data sent after close is answered with RST, which is reported first.
+    let mut result = Ok(0);
+    for _ in 0..10 {
+        result = client.send(&data, 0);
+        match result {
+            Err(ref error) if error.kind() == std::io::ErrorKind::BrokenPipe => break,
+            _ => thread::sleep(time::Duration::from_millis(10))
+        }
+    }
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+    assert!(client.nosigpipe());
+
+    //sendfile has no flags, so signal is blocked during the call instead.
+    let path = std::env::temp_dir().join(format!("lazy_socket_no_sigpipe_{}", std::process::id()));
+    std::fs::File::create(&path).unwrap().write_all(&data).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    let result = client.send_file(&file, 0, data.len());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+
+    //Adopted descriptor is protected as well.
+    let client = unsafe { Socket::from_raw_fd(client.into_raw_fd()) };
+    assert!(client.nosigpipe());
+    let result = client.send(&data, 0);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+
+    assert_eq!(SIGPIPE_COUNT.load(Ordering::SeqCst), 0);
+
+    //Signal is raised once allowed.
+    assert!(client.set_nosigpipe(false).is_ok());
+    assert!(!client.nosigpipe());
+    let result = client.send(&data, 0);
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+    assert_eq!(SIGPIPE_COUNT.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn socket_test_error_class() {
+    let server = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let server_addr = server.name().unwrap();
+
+    //Not listening so connection is refused.
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    let error = client.connect(&server_addr).err().unwrap();
+    assert_eq!(error.operation(), Operation::Connect);
+    assert_eq!(error.class(), ErrorClass::PeerClosed);
+    assert!(error.raw_os_error().is_some());
+    assert!(error.to_string().starts_with("connect failed: "));
+
+    let error: std::io::Error = error.into();
+    assert_eq!(error.kind(), std::io::ErrorKind::ConnectionRefused);
+    assert_eq!(error.class(), ErrorClass::PeerClosed);
+
+    let other = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    let error = other.bind(&server_addr).err().unwrap();
+    assert_eq!(error.operation(), Operation::Bind);
+    assert_eq!(error.class(), ErrorClass::Address);
+
+    let error = other.set_read_timeout(Some(time::Duration::from_secs(0))).operation(Operation::SetOpt).err().unwrap();
+    assert_eq!(error.operation(), Operation::SetOpt);
+    assert_eq!(error.class(), ErrorClass::Programmer);
+
+    assert!(server.listen(1).is_ok());
+    assert!(server.set_blocking(false).is_ok());
+    let error = server.accept().err().unwrap();
+    assert_eq!(error.operation(), Operation::Accept);
+    assert_eq!(error.class(), ErrorClass::Transient);
+    assert!(error.is_transient());
+
+    let error = other.send(&[1], 0).err().unwrap();
+    assert_eq!(error.operation(), Operation::Send);
+    let error = other.recv(&mut [0], 0).err().unwrap();
+    assert_eq!(error.operation(), Operation::Recv);
+    let error = other.get_opt::<c_int>(-1, -1).err().unwrap();
+    assert_eq!(error.operation(), Operation::GetOpt);
+}
+
+#[test]
+fn socket_test_recv_uninit() {
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let client_addr = client.name().unwrap();
+
+    let mut buf = vec![std::mem::MaybeUninit::<u8>::uninit(); 64 * 1024];
+
+    assert_eq!(client.send_to(b"first", &server_addr, 0).unwrap(), 5);
+    let len = server.recv_uninit(&mut buf, 0).unwrap();
+    assert_eq!(len, 5);
+    let data: Vec<u8> = buf[..len].iter().map(|byte| unsafe { byte.assume_init() }).collect();
+    assert_eq!(data, b"first");
+
+    assert_eq!(client.send_to(b"second", &server_addr, 0).unwrap(), 6);
+    let (len, addr) = server.recv_from_uninit(&mut buf, 0).unwrap();
+    assert_eq!(len, 6);
+    assert_eq!(addr, client_addr);
+    let data: Vec<u8> = buf[..len].iter().map(|byte| unsafe { byte.assume_init() }).collect();
+    assert_eq!(data, b"second");
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        let mut small = [std::mem::MaybeUninit::<u8>::uninit(); 4];
+
+        assert_eq!(client.send_to(b"0123456789", &server_addr, 0).unwrap(), 10);
+        assert_eq!(server.recv_uninit(&mut small, libc::MSG_TRUNC).unwrap(), 4);
+
+        assert_eq!(client.send_to(b"0123456789", &server_addr, 0).unwrap(), 10);
+        assert_eq!(server.recv_from_uninit(&mut small, libc::MSG_TRUNC).unwrap(), (4, client_addr));
+
+        assert_eq!(client.send_to(b"0123456789", &server_addr, 0).unwrap(), 10);
+        assert_eq!(server.recv(&mut [0u8; 4], libc::MSG_TRUNC).unwrap(), 10);
+    }
+}
+
+#[test]
+fn socket_test_peek_from() {
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let client_addr = client.name().unwrap();
+
+    assert_eq!(client.send_to(b"0123456789", &server_addr, 0).unwrap(), 10);
+
+    let mut buf = [0u8; 4];
+    assert_eq!(server.peek_from(&mut buf).unwrap(), (4, client_addr));
+    assert_eq!(&buf, b"0123");
+
+    #[cfg(target_os = "linux")]
+    {
+        assert_eq!(server.datagram_size().unwrap(), 10);
+        assert_eq!(server.recv_truncating(&mut buf, 0).unwrap(), (4, 10));
+        assert_eq!(&buf, b"0123");
+
+        //Datagram that exactly fills buffer is not truncated.
+        assert_eq!(client.send_to(b"abcd", &server_addr, 0).unwrap(), 4);
+        assert_eq!(server.datagram_size().unwrap(), 4);
+        assert_eq!(server.recv_truncating(&mut buf, 0).unwrap(), (4, 4));
+        assert_eq!(&buf, b"abcd");
+    }
+}
+
+#[test]
+fn socket_test_queue_depth() {
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let server_addr = server.name().unwrap();
+    assert_eq!(server.bytes_available().unwrap(), 0);
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert_eq!(client.send_to(b"0123456789", &server_addr, 0).unwrap(), 10);
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let mut buf = [0u8; 1];
+    assert!(server.peek_from(&mut buf).is_ok());
+    assert_eq!(server.bytes_available().unwrap(), 10);
+
+    #[cfg(target_os = "linux")]
+    {
+        let listener = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+        assert!(listener.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+        assert!(listener.listen(1).is_ok());
+
+        let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+        assert!(client.connect(&listener.name().unwrap()).is_ok());
+        let (server, _) = listener.accept().unwrap();
+
+        assert_eq!(client.send(b"0123456789", 0).unwrap(), 10);
+        assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+        assert!(server.recv(&mut buf, MsgFlags::PEEK).is_ok());
+        assert_eq!(server.bytes_available().unwrap(), 10);
+
+        //Loopback acknowledges immediately, so nothing should stay queued for long.
+        let now = time::Instant::now();
+        while client.unsent_bytes().unwrap() + client.unacked_bytes().unwrap() > 0 {
+            assert!(now.elapsed() < time::Duration::from_secs(1));
+            thread::sleep(time::Duration::from_millis(1));
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_send_file_and_splice() {
+    use std::io::Write;
+
+    let data: Vec<u8> = (0..256 * 1024).map(|idx| (idx % 251) as u8).collect();
+    let path = std::env::temp_dir().join(format!("lazy_socket_send_file_{}", std::process::id()));
+    std::fs::File::create(&path).unwrap().write_all(&data).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let connect_pair = || {
+        let listener = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+        assert!(listener.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+        assert!(listener.listen(1).is_ok());
+
+        let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+        assert!(client.connect(&listener.name().unwrap()).is_ok());
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    };
+
+    //Source -> relay_in, relay_out -> sink.
+    let (source, relay_in) = connect_pair();
+    let (relay_out, sink) = connect_pair();
+
+    let offset = 1000;
+    let sender = thread::spawn(move || {
+        let mut sent = 0;
+        while offset + sent < data.len() {
+            let len = source.send_file(&file, (offset + sent) as u64, data.len()).unwrap();
+            assert!(len > 0);
+            sent += len;
+        }
+        assert_eq!(source.send_file(&file, data.len() as u64, 1).unwrap(), 0);
+        assert!(source.shutdown(ShutdownType::Send).is_ok());
+        data
+    });
+
+    let relay = thread::spawn(move || {
+        let mut pipe = SplicePipe::new().unwrap();
+        let mut total = 0;
+        loop {
+            match pipe.transfer(&relay_in, &relay_out, 16 * 1024).unwrap() {
+                0 => break,
+                len => total += len
+            }
+        }
+        assert_eq!(pipe.pending(), 0);
+        assert!(relay_out.shutdown(ShutdownType::Send).is_ok());
+        total
+    });
+
+    let mut received = Vec::new();
+    let mut buf = [0u8; 8192];
+    loop {
+        match sink.recv(&mut buf, 0).unwrap() {
+            0 => break,
+            len => received.extend_from_slice(&buf[..len])
+        }
+    }
+
+    let data = sender.join().unwrap();
+    assert_eq!(relay.join().unwrap(), data.len() - offset);
+    assert_eq!(received, &data[offset..]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_send_zerocopy() {
+    let listener = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    assert!(listener.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(listener.listen(1).is_ok());
+
+    let client = Socket::new(Family::IPv4, Type::STREAM, Protocol::TCP).unwrap();
+    if client.enable_zerocopy().is_err() {
+        //Kernel without SO_ZEROCOPY.
+        return;
+    }
+    assert!(client.connect(&listener.name().unwrap()).is_ok());
+    let (server, _) = listener.accept().unwrap();
+
+    assert!(client.recv_extended_error().unwrap().is_none());
+
+    //Empty buffer is not sent and doesn't consume identifier.
+    assert_eq!(client.send_zerocopy(&[], 0).unwrap(), (0, None));
+
+    let data = vec![1u8; 64 * 1024];
+    let mut sent = Vec::new();
+    for _ in 0..3 {
+        let (len, id) = client.send_zerocopy(&data, 0).unwrap();
+        assert_eq!(len, data.len());
+        sent.push(id.unwrap());
+    }
+    assert_eq!(sent, vec![0, 1, 2]);
+
+    let mut buf = vec![0u8; 3 * data.len()];
+    assert!(server.recv_exact(&mut buf, 0, None).is_ok());
+
+    let mut completed = Vec::new();
+    let now = time::Instant::now();
+    while completed.len() < 3 {
+        assert!(now.elapsed() < time::Duration::from_secs(2));
+
+        match client.recv_extended_error().unwrap() {
+            Some(error) => {
+                assert_eq!(error.origin(), ErrorOrigin::Zerocopy);
+                assert!(error.error().is_none());
+                let completion = error.zerocopy().unwrap();
+                for id in completion.first..=completion.last {
+                    assert!(completion.contains(id));
+                    completed.push(id);
+                }
+                //Loopback always copies.
+                assert!(completion.copied);
+            },
+            None => thread::sleep(time::Duration::from_millis(1))
+        }
+    }
+
+    assert_eq!(completed, sent);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_udp_segment_gro() {
+    let data: Vec<u8> = (0..9500).map(|idx| (idx % 251) as u8).collect();
+
+    let plain = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(plain.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(plain.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let gro = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(gro.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(gro.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    assert!(!gro.udp_gro().unwrap());
+    assert!(gro.set_udp_gro(true).is_ok());
+    assert!(gro.udp_gro().unwrap());
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let client_addr = client.name().unwrap();
+
+    //Without GRO receiver gets individual datagrams.
+    assert_eq!(client.send_to_segmented(&data, &plain.name().unwrap(), 1000, 0).unwrap(), data.len());
+    let mut buf = [0u8; 65535];
+    for chunk in data.chunks(1000) {
+        let (segments, addr) = plain.recv_from_gro(&mut buf, 0).unwrap();
+        assert_eq!(addr, client_addr);
+        assert_eq!(segments.segment_size(), chunk.len());
+        assert!(!segments.truncated());
+        assert_eq!(segments.collect::<Vec<_>>(), vec![chunk]);
+    }
+
+    //Empty datagram is single empty segment.
+    assert_eq!(client.send_to(&[], &plain.name().unwrap(), 0).unwrap(), 0);
+    let (segments, _) = plain.recv_from_gro(&mut buf, 0).unwrap();
+    assert_eq!(segments.segment_size(), 0);
+    assert_eq!(segments.collect::<Vec<_>>(), vec![&[][..]]);
+
+    //Excess bytes are reported.
+    assert_eq!(client.send_to(&data[..1000], &plain.name().unwrap(), 0).unwrap(), 1000);
+    let (segments, _) = plain.recv_from_gro(&mut buf[..10], 0).unwrap();
+    assert!(segments.truncated());
+    assert_eq!(segments.data(), &data[..10]);
+
+    //Socket option applies to every send.
+    assert!(client.udp_segment().unwrap().is_none());
+    assert!(client.set_udp_segment(Some(1000)).is_ok());
+    assert_eq!(client.udp_segment().unwrap(), Some(1000));
+    assert_eq!(client.send_to(&data, &gro.name().unwrap(), 0).unwrap(), data.len());
+
+    let mut received = Vec::new();
+    while received.len() < data.len() {
+        let (segments, addr) = gro.recv_from_gro(&mut buf, 0).unwrap();
+        assert_eq!(addr, client_addr);
+        assert!(!segments.truncated());
+        for segment in segments {
+            assert!(segment.len() <= 1000);
+            received.extend_from_slice(segment);
+        }
+    }
+    assert_eq!(received, data);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_timestamps() {
+    let is_recent = |time: time::SystemTime| {
+        let now = time::SystemTime::now();
+        time <= now && now.duration_since(time).unwrap() < time::Duration::from_secs(5)
+    };
+
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    let client_addr = client.name().unwrap();
+
+    let mut buf = [0u8; 16];
+
+    //No timestamps unless enabled.
+    assert_eq!(client.send_to(b"none", &server_addr, 0).unwrap(), 4);
+    assert_eq!(server.recv_from_timestamped(&mut buf, 0).unwrap(), (4, client_addr, None));
+
+    assert!(!server.timestamp_ns().unwrap());
+    assert!(server.set_timestamp_ns(true).is_ok());
+    assert!(server.timestamp_ns().unwrap());
+    assert_eq!(client.send_to(b"ns", &server_addr, 0).unwrap(), 2);
+    let (len, addr, timestamp) = server.recv_from_timestamped(&mut buf, 0).unwrap();
+    assert_eq!((len, addr), (2, client_addr));
+    assert!(is_recent(timestamp.unwrap()));
+    assert!(server.set_timestamp_ns(false).is_ok());
+
+    let rx = Timestamping::RX_SOFTWARE | Timestamping::SOFTWARE;
+    assert!(server.set_timestamping(rx).is_ok());
+    assert_eq!(server.timestamping().unwrap(), rx);
+
+    //Kernel enables RX timestamps asynchronously, so first datagrams may lack them.
+    let now = time::Instant::now();
+    loop {
+        assert!(now.elapsed() < time::Duration::from_secs(2));
+
+        assert_eq!(client.send_to(b"rx", &server_addr, 0).unwrap(), 2);
+        let (len, _, timestamp) = server.recv_from_timestamped(&mut buf, 0).unwrap();
+        assert_eq!(len, 2);
+        if let Some(timestamp) = timestamp {
+            assert!(is_recent(timestamp));
+            break;
+        }
+    }
+
+    let tx = Timestamping::TX_SOFTWARE | Timestamping::SOFTWARE | Timestamping::OPT_ID | Timestamping::OPT_TSONLY;
+    assert!(client.set_timestamping(tx).is_ok());
+
+    //Once enabled every datagram carries timestamp.
+    for _ in 0..3 {
+        assert_eq!(client.send_to(b"ts", &server_addr, 0).unwrap(), 2);
+        let (len, _, timestamp) = server.recv_from_timestamped(&mut buf, 0).unwrap();
+        assert_eq!(len, 2);
+        assert!(is_recent(timestamp.unwrap()));
+    }
+
+    let mut ids = Vec::new();
+    let now = time::Instant::now();
+    while ids.len() < 3 {
+        assert!(now.elapsed() < time::Duration::from_secs(2));
+
+        match client.recv_extended_error().unwrap() {
+            Some(error) => {
+                assert_eq!(error.origin(), ErrorOrigin::TxStatus);
+                let timestamp = error.tx_timestamp().unwrap();
+                assert!(is_recent(timestamp.time));
+                ids.push(timestamp.id);
+            },
+            None => thread::sleep(time::Duration::from_millis(1))
+        }
+    }
+    assert_eq!(ids, vec![0, 1, 2]);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_pktinfo() {
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("0.0.0.0:0").unwrap()).is_ok());
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    assert!(!server.pktinfo_v4().unwrap());
+    assert!(server.set_pktinfo_v4(true).is_ok());
+    assert!(server.pktinfo_v4().unwrap());
+    let port = server.name().unwrap().port();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(client.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let client_addr = client.name().unwrap();
+
+    let mut buf = [0u8; 16];
+    for local in &["127.0.0.1", "127.0.0.2"] {
+        let local = net::IpAddr::from_str(local).unwrap();
+        let server_addr = net::SocketAddr::new(local, port);
+
+        assert_eq!(client.send_to(b"ping", &server_addr, 0).unwrap(), 4);
+        let (len, addr, info) = server.recv_from_with_pktinfo(&mut buf, 0).unwrap();
+        assert_eq!((len, addr), (4, client_addr));
+        let info = info.unwrap();
+        assert_eq!(info.addr, local);
+        assert!(info.interface > 0);
+
+        //Reply comes from address request was sent to.
+        assert_eq!(server.send_to_with_pktinfo(b"pong", &addr, &info, 0).unwrap(), 4);
+        assert_eq!(client.recv_from(&mut buf, 0).unwrap(), (4, server_addr));
+        assert_eq!(&buf[..4], b"pong");
+    }
+
+    let server = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    if server.bind(&net::SocketAddr::from_str("[::]:0").unwrap()).is_err() {
+        //IPv6 is unavailable.
+        return;
+    }
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    assert!(server.set_pktinfo_v6(true).is_ok());
+    assert!(server.pktinfo_v6().unwrap());
+    let server_addr = net::SocketAddr::new(net::IpAddr::from_str("::1").unwrap(), server.name().unwrap().port());
+
+    let client = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("[::1]:0").unwrap()).is_ok());
+    assert!(client.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let client_addr = client.name().unwrap();
+
+    assert_eq!(client.send_to(b"ping", &server_addr, 0).unwrap(), 4);
+    let (len, addr, info) = server.recv_from_with_pktinfo(&mut buf, 0).unwrap();
+    assert_eq!((len, addr), (4, client_addr));
+    let info = info.unwrap();
+    assert_eq!(info.addr, server_addr.ip());
+
+    assert_eq!(server.send_to_with_pktinfo(b"pong", &addr, &info, 0).unwrap(), 4);
+    assert_eq!(client.recv_from(&mut buf, 0).unwrap(), (4, server_addr));
+
+    //IPv4 request to dual-stack socket is reported and replied to with plain IPv4 addresses.
+    let server = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind_dual_stack(0).is_ok());
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    assert!(server.set_pktinfo_v6(true).is_ok());
+    let server_addr = net::SocketAddr::from_str(&format!("127.0.0.2:{}", server.name().unwrap().port())).unwrap();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(client.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let client_addr = client.name().unwrap();
+
+    assert_eq!(client.send_to(b"ping", &server_addr, 0).unwrap(), 4);
+    let (len, addr, info) = server.recv_from_with_pktinfo(&mut buf, 0).unwrap();
+    assert_eq!((len, addr), (4, client_addr));
+    let info = info.unwrap();
+    assert_eq!(info.addr, server_addr.ip());
+
+    assert_eq!(server.send_to_with_pktinfo(b"pong", &addr, &info, 0).unwrap(), 4);
+    assert_eq!(client.recv_from(&mut buf, 0).unwrap(), (4, server_addr));
+    assert_eq!(&buf[..4], b"pong");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_recv_error() {
+    //Grab free port and release it so that nobody listens on it.
+    let server_addr = {
+        let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+        assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+        server.name().unwrap()
+    };
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(!client.recv_error_v4().unwrap());
+    assert!(client.set_recv_error_v4(true).is_ok());
+    assert!(client.recv_error_v4().unwrap());
+
+    let mut buf = [0u8; 16];
+    assert!(client.recv_error(&mut buf).unwrap().is_none());
+    assert_eq!(client.send_to(b"probe", &server_addr, 0).unwrap(), 5);
+
+    let now = time::Instant::now();
+    let message = loop {
+        assert!(now.elapsed() < time::Duration::from_secs(2));
+
+        match client.recv_error(&mut buf).unwrap() {
+            Some(message) => break message,
+            None => thread::sleep(time::Duration::from_millis(1))
+        }
+    };
+
+    assert_eq!(&buf[..message.len], b"probe");
+    assert!(!message.truncated);
+    let error = message.error;
+    assert_eq!(error.origin(), ErrorOrigin::Icmp);
+    assert_eq!(error.error().unwrap().kind(), std::io::ErrorKind::ConnectionRefused);
+    assert_eq!(error.errno(), libc::ECONNREFUSED as u32);
+    //Destination unreachable, port unreachable.
+    assert_eq!((error.kind(), error.code()), (3, 3));
+    assert_eq!(error.offender().unwrap().ip(), server_addr.ip());
+    assert!(error.zerocopy().is_none());
+    assert!(error.tx_timestamp().is_none());
+
+    //Packet that doesn't fit into buffer is reported as truncated.
+    assert_eq!(client.send_to(b"longer probe", &server_addr, 0).unwrap(), 12);
+    let mut small = [0u8; 4];
+    let now = time::Instant::now();
+    let message = loop {
+        assert!(now.elapsed() < time::Duration::from_secs(2));
+
+        match client.recv_error(&mut small).unwrap() {
+            Some(message) => break message,
+            None => thread::sleep(time::Duration::from_millis(1))
+        }
+    };
+
+    assert_eq!((message.len, &small), (4, b"long"));
+    assert!(message.truncated);
+    assert_eq!(message.error.origin(), ErrorOrigin::Icmp);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_ip_header_fields() {
+    let class = TrafficClass::new(46, Ecn::Ect0);
+    assert_eq!(class.raw(), 0xba);
+    assert_eq!((class.dscp(), class.ecn()), (46, Ecn::Ect0));
+    assert_eq!(TrafficClass::from_raw(0xbb).ecn(), Ecn::Ce);
+
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let server_addr = server.name().unwrap();
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.set_ttl(42).is_ok());
+    assert_eq!(client.ttl().unwrap(), 42);
+    assert!(client.set_tos(class).is_ok());
+    assert_eq!(client.tos().unwrap(), class);
+
+    let mut buf = [0u8; 16];
+    assert_eq!(client.send_to(b"none", &server_addr, 0).unwrap(), 4);
+    assert_eq!(server.recv_from_with_header(&mut buf, 0).unwrap().2, IpHeader::default());
+
+    assert!(server.set_recv_ttl(true).is_ok());
+    assert!(server.set_recv_tos(true).is_ok());
+
+    //Congestion experienced mark set by sender is observed by receiver.
+    assert!(client.set_tos(TrafficClass::new(46, Ecn::Ce)).is_ok());
+    assert_eq!(client.send_to(b"mark", &server_addr, 0).unwrap(), 4);
+    let (len, _, header) = server.recv_from_with_header(&mut buf, 0).unwrap();
+    assert_eq!(len, 4);
+    assert_eq!(header.hop_limit, Some(42));
+    let received = header.traffic_class.unwrap();
+    assert_eq!((received.dscp(), received.ecn()), (46, Ecn::Ce));
+
+    let server = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    if server.bind(&net::SocketAddr::from_str("[::1]:0").unwrap()).is_err() {
+        //IPv6 is unavailable.
+        return;
+    }
+    let server_addr = server.name().unwrap();
+
+    let client = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.set_unicast_hops_v6(7).is_ok());
+    assert_eq!(client.unicast_hops_v6().unwrap(), 7);
+    assert!(client.set_tclass_v6(class).is_ok());
+    assert_eq!(client.tclass_v6().unwrap(), class);
+
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    assert!(server.set_recv_hop_limit_v6(true).is_ok());
+    assert!(server.set_recv_tclass_v6(true).is_ok());
+
+    assert_eq!(client.send_to(b"mark", &server_addr, 0).unwrap(), 4);
+    let (_, _, header) = server.recv_from_with_header(&mut buf, 0).unwrap();
+    assert_eq!(header.hop_limit, Some(7));
+    assert_eq!(header.traffic_class, Some(class));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_path_mtu() {
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("127.0.0.1:0").unwrap()).is_ok());
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert_eq!(client.path_mtu_v4().err().unwrap().kind(), std::io::ErrorKind::NotConnected);
+    assert!(client.set_mtu_discover_v4(PmtuDiscovery::Do).is_ok());
+    assert_eq!(client.mtu_discover_v4().unwrap(), PmtuDiscovery::Do);
+    assert!(client.set_mtu_discover_v4(PmtuDiscovery::Interface).is_ok());
+    assert_eq!(client.mtu_discover_v4().unwrap(), PmtuDiscovery::Interface);
+    assert!(client.connect(&server.name().unwrap()).is_ok());
+    assert!(client.path_mtu_v4().unwrap() >= 576);
+
+    let server = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    if server.bind(&net::SocketAddr::from_str("[::1]:0").unwrap()).is_err() {
+        //IPv6 is unavailable.
+        return;
+    }
+
+    let client = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.set_mtu_discover_v6(PmtuDiscovery::Do).is_ok());
+    assert_eq!(client.mtu_discover_v6().unwrap(), PmtuDiscovery::Do);
+    assert!(client.set_recv_error_v6(true).is_ok());
+    assert!(client.connect(&server.name().unwrap()).is_ok());
+    let mtu = client.path_mtu_v6().unwrap();
+    assert!(mtu >= 1280);
+
+    //Datagram that doesn't fit into loopback's MTU is rejected and reported with MTU.
+    let data = vec![0u8; 65500];
+    if mtu as usize >= data.len() + 48 {
+        return;
+    }
+    let error = client.send(&data, 0).err().unwrap();
+    assert_eq!(error.raw_os_error(), Some(libc::EMSGSIZE));
+    let error = client.recv_extended_error().unwrap().unwrap();
+    assert_eq!(error.path_mtu(), Some(mtu));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_multicast() {
+    let group = net::Ipv4Addr::new(239, 255, 42, 99);
+    let local = net::Ipv4Addr::new(127, 0, 0, 1);
+    let loopback = unsafe { libc::if_nametoindex(b"lo\0".as_ptr() as *const c_char) };
+    assert!(loopback != 0);
+
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("0.0.0.0:0").unwrap()).is_ok());
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let port = server.name().unwrap().port();
+    let dest = net::SocketAddr::V4(net::SocketAddrV4::new(group, port));
+
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.set_multicast_if_v4(&local).is_ok());
+    assert_eq!(client.multicast_if_v4().unwrap(), local);
+    assert!(client.set_multicast_ttl_v4(2).is_ok());
+    assert_eq!(client.multicast_ttl_v4().unwrap(), 2);
+    assert!(client.set_multicast_loop_v4(true).is_ok());
+    assert!(client.multicast_loop_v4().unwrap());
+
+    let mut buf = [0u8; 16];
+
+    assert!(server.join_multicast_v4(&group, &local).is_ok());
+    assert_eq!(client.send_to(b"by-addr", &dest, 0).unwrap(), 7);
+    let (len, addr) = server.recv_from(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..len], b"by-addr");
+    assert_eq!(addr.ip(), net::IpAddr::V4(local));
+    assert!(server.leave_multicast_v4(&group, &local).is_ok());
+    //Group is no longer joined.
+    assert!(server.leave_multicast_v4(&group, &local).is_err());
+
+    assert!(server.join_multicast_v4_index(&group, loopback).is_ok());
+    assert_eq!(client.send_to(b"by-index", &dest, 0).unwrap(), 8);
+    let (len, _) = server.recv_from(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..len], b"by-index");
+    assert!(server.leave_multicast_v4_index(&group, loopback).is_ok());
+
+    //Source-specific membership only accepts datagrams from listed source.
+    let ssm_group = net::Ipv4Addr::new(232, 1, 2, 3);
+    let ssm_dest = net::SocketAddr::V4(net::SocketAddrV4::new(ssm_group, port));
+    assert!(server.join_source_multicast_v4(&ssm_group, &net::Ipv4Addr::new(127, 0, 0, 2), &local).is_ok());
+    assert_eq!(client.send_to(b"filtered", &ssm_dest, 0).unwrap(), 8);
+    assert!(server.recv_from(&mut buf, 0).is_err());
+    assert!(server.leave_source_multicast_v4(&ssm_group, &net::Ipv4Addr::new(127, 0, 0, 2), &local).is_ok());
+
+    assert!(server.join_source_multicast_v4(&ssm_group, &local, &local).is_ok());
+    assert_eq!(client.send_to(b"ssm", &ssm_dest, 0).unwrap(), 3);
+    let (len, _) = server.recv_from(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..len], b"ssm");
+    assert!(server.leave_source_multicast_v4(&ssm_group, &local, &local).is_ok());
+
+    let server = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    if server.bind(&net::SocketAddr::from_str("[::]:0").unwrap()).is_err() {
+        //IPv6 is unavailable.
+        return;
+    }
+
+    let client = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.set_multicast_if_v6(loopback).is_ok());
+    assert_eq!(client.multicast_if_v6().unwrap(), loopback);
+    assert!(client.set_multicast_hops_v6(5).is_ok());
+    assert_eq!(client.multicast_hops_v6().unwrap(), 5);
+    assert!(client.set_multicast_loop_v6(false).is_ok());
+    assert!(!client.multicast_loop_v6().unwrap());
+    assert!(client.set_multicast_loop_v6(true).is_ok());
+    assert!(client.multicast_loop_v6().unwrap());
+
+    let group = net::Ipv6Addr::new(0xff12, 0, 0, 0, 0, 0, 0, 0x4242);
+    //Delivery is covered by socket_test_multicast_v6_delivery.
+    assert!(server.join_multicast_v6(&group, loopback).is_ok());
+    assert!(server.leave_multicast_v6(&group, loopback).is_ok());
+    //Group is no longer joined.
+    assert!(server.leave_multicast_v6(&group, loopback).is_err());
+
+    let ssm_group = net::Ipv6Addr::new(0xff3e, 0, 0, 0, 0, 0, 0, 0x4242);
+    assert!(server.join_source_multicast_v6(&ssm_group, &net::Ipv6Addr::LOCALHOST, loopback).is_ok());
+    assert!(server.leave_source_multicast_v6(&ssm_group, &net::Ipv6Addr::LOCALHOST, loopback).is_ok());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+#[ignore = "requires interface with IPv6 multicast route, kernel installs none for loopback"]
+fn socket_test_multicast_v6_delivery() {
+    let server = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("[::]:0").unwrap()).is_ok());
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let port = server.name().unwrap().port();
+
+    //Interface 0 lets kernel pick one by route.
+    let client = Socket::new(Family::IPv6, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(client.set_multicast_if_v6(0).is_ok());
+    assert!(client.set_multicast_loop_v6(true).is_ok());
+
+    let group = net::Ipv6Addr::new(0xff15, 0, 0, 0, 0, 0, 0, 0x4242);
+    let dest = net::SocketAddr::V6(net::SocketAddrV6::new(group, port, 0, 0));
+    let mut buf = [0u8; 16];
+
+    assert!(server.join_multicast_v6(&group, 0).is_ok());
+    assert_eq!(client.send_to(b"v6", &dest, 0).unwrap(), 2);
+    let (len, _) = server.recv_from(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..len], b"v6");
+    assert!(server.leave_multicast_v6(&group, 0).is_ok());
+
+    //Looped back copy is no longer delivered.
+    assert_eq!(client.send_to(b"v6", &dest, 0).unwrap(), 2);
+    assert!(server.recv_from(&mut buf, 0).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn socket_test_broadcast() {
+    let client = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(!client.broadcast().unwrap());
+    assert!(client.set_broadcast(true).is_ok());
+    assert!(client.broadcast().unwrap());
+    assert!(client.set_broadcast(false).is_ok());
+    assert!(!client.broadcast().unwrap());
+
+    let interfaces = interface_broadcasts().unwrap();
+    for interface in interfaces.iter() {
+        assert!(!interface.name.is_empty());
+        assert!(!interface.addr.is_loopback());
+        assert!(interface.broadcast != interface.addr);
+    }
+
+    let interface = match interfaces.first() {
+        Some(interface) => interface,
+        //No broadcast capable interfaces.
+        None => return
+    };
+
+    let server = Socket::new(Family::IPv4, Type::DATAGRAM, Protocol::UDP).unwrap();
+    assert!(server.bind(&net::SocketAddr::from_str("0.0.0.0:0").unwrap()).is_ok());
+    assert!(server.set_read_timeout(Some(time::Duration::from_secs(1))).is_ok());
+    let dest = net::SocketAddr::V4(net::SocketAddrV4::new(interface.broadcast, server.name().unwrap().port()));
+
+    let error = client.send_to(b"denied", &dest, 0).err().unwrap();
+    assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+
+    assert!(client.set_broadcast(true).is_ok());
+    assert_eq!(client.send_to(b"hello", &dest, 0).unwrap(), 5);
+    let mut buf = [0u8; 16];
+    let (len, _) = server.recv_from(&mut buf, 0).unwrap();
+    assert_eq!(&buf[..len], b"hello");
+}
+
+#[cfg(windows)]
+#[test]
+fn socket_test_interface_broadcasts() {
+    for interface in interface_broadcasts().unwrap() {
+        assert!(!interface.name.is_empty());
+        assert!(!interface.addr.is_loopback());
+        assert!(interface.broadcast != interface.addr);
+    }
+}
+
 #[cfg(windows)]
 #[test]
 fn socket_as_into_from_traits() {